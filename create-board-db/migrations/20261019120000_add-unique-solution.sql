@@ -0,0 +1,2 @@
+-- Whether the optimal solution is unique up to move commutation
+alter table solved_positions add column unique_solution integer not null default 0;
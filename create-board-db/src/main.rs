@@ -1,9 +1,12 @@
 use inertia_core::board_generators::ClassicBoardGenerator;
+use inertia_core::board_generators::ClassicGeneratorConfig;
 use inertia_core::mechanics::B64EncodedCompressedPosition;
 use inertia_core::mechanics::CompressedPosition;
 use inertia_core::mechanics::PositionGenerator;
+use inertia_core::mechanics::SolvedPosition;
 use inertia_core::solvers::astar::solve_position;
 use inertia_core::solvers::get_solution_internal_difficulty;
+use inertia_core::solvers::is_solved_position_unique;
 use inertia_core::solvers::CompressedSolution;
 use inertia_core::solvers::Difficulty;
 use inertia_core::solvers::Solution;
@@ -23,6 +26,19 @@ const POSITIONS_PER_INTERNAL_DIFFICULTY: usize = 500;
 
 #[tokio::main]
 async fn main() -> Result<(), sqlx::Error> {
+  // An optional JSON file of generator parameters. Unset fields keep the
+  // classic defaults.
+  let generator_config = match std::env::args().nth(1) {
+    Some(config_path) => {
+      let config_json = std::fs::read_to_string(&config_path)
+        .expect("Failed to read generator config");
+      serde_json::from_str::<ClassicGeneratorConfig>(&config_json)
+        .expect("Failed to parse generator config")
+    }
+    None => ClassicGeneratorConfig::default(),
+  };
+  let generator = ClassicBoardGenerator::with_config(generator_config);
+
   let conn = Arc::new(Mutex::new(SqliteConnection::connect(DB_URL).await?));
 
   let internal_difficulty_counts = [
@@ -54,7 +70,7 @@ async fn main() -> Result<(), sqlx::Error> {
       for _ in 0..BATCH_SIZE {
         scope.spawn(async {
           let thread_conn = conn.clone();
-          let position = ClassicBoardGenerator::new().generate_position();
+          let position = generator.generate_position();
           let compressed_position = CompressedPosition::from(position);
 
           let start = Instant::now();
@@ -84,13 +100,18 @@ async fn main() -> Result<(), sqlx::Error> {
           let difficulty_count = difficulty_counts[u8::from(difficulty) as usize]
             .fetch_add(1, Ordering::SeqCst);
 
+          let solved_position = SolvedPosition { position, solution };
+          let unique_solution = is_solved_position_unique(&solved_position);
+          let SolvedPosition { solution, .. } = solved_position;
+
           sqlx::query(
-            "insert into solved_positions (position, solution, difficulty, difficulty_ordinal) values (?, ?, ?, ?)",
+            "insert into solved_positions (position, solution, difficulty, difficulty_ordinal, unique_solution) values (?, ?, ?, ?, ?)",
           )
           .bind(compressed_position.0.as_slice())
           .bind(CompressedSolution::from(solution).0)
           .bind(u8::from(difficulty))
           .bind(difficulty_count as u32)
+          .bind(unique_solution)
           .execute(thread_conn.lock().await.deref_mut())
           .await
           .unwrap();
@@ -0,0 +1,74 @@
+use std::fs;
+use std::path::PathBuf;
+
+use clap::Parser;
+use inertia_core::mechanics::B64EncodedCompressedPosition;
+use inertia_core::mechanics::CompressedPosition;
+use inertia_core::mechanics::Position;
+use inertia_core::solvers::Difficulty;
+use inertia_core::solvers::DifficultyFeatures;
+use inertia_core::solvers::DifficultyModel;
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+struct LabelledPosition {
+  position: B64EncodedCompressedPosition,
+  difficulty: Difficulty,
+}
+
+#[derive(Parser, Debug)]
+#[command(version, about, long_about = None)]
+struct Args {
+  /// JSON file holding a list of { "position": <b64>, "difficulty": <label> }
+  labels: PathBuf,
+  /// JSON file holding a model to start from. Uses the default model if unset.
+  #[arg(long)]
+  model: Option<PathBuf>,
+  #[arg(long, default_value_t = 45)]
+  max_depth: usize,
+}
+
+fn main() {
+  let args = Args::parse();
+
+  let labelled_positions: Vec<LabelledPosition> = serde_json::from_str(
+    &fs::read_to_string(&args.labels).expect("Unable to read labels file"),
+  )
+  .expect("Unable to parse labels file");
+
+  let mut model: DifficultyModel = match args.model {
+    Some(path) => serde_json::from_str(
+      &fs::read_to_string(path).expect("Unable to read model file"),
+    )
+    .expect("Unable to parse model file"),
+    None => DifficultyModel::default(),
+  };
+
+  let mut samples = Vec::with_capacity(labelled_positions.len());
+  for LabelledPosition {
+    position,
+    difficulty,
+  } in labelled_positions
+  {
+    let decoded = CompressedPosition::try_from(&position)
+      .ok()
+      .and_then(|compressed| Position::try_from(compressed).ok());
+    let Some(decoded) = decoded else {
+      eprintln!("Skipping undecodable position {}", position.0);
+      continue;
+    };
+    match DifficultyFeatures::from_position(decoded, args.max_depth) {
+      Ok((features, _)) => samples.push((features, difficulty)),
+      Err(reason) => eprintln!("Skipping position {}: {}", position.0, reason),
+    }
+  }
+
+  eprintln!("Accuracy before fitting: {:.3}", model.accuracy(&samples));
+  model.fit_thresholds(&samples);
+  eprintln!("Accuracy after fitting: {:.3}", model.accuracy(&samples));
+
+  println!(
+    "{}",
+    serde_json::to_string_pretty(&model).expect("Model is serializable")
+  );
+}
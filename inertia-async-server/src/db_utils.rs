@@ -5,12 +5,15 @@ use inertia_core::mechanics::PositionConvertError;
 use inertia_core::mechanics::SolvedPosition;
 use inertia_core::solvers::CompressedSolution;
 use inertia_core::solvers::Difficulty;
+use inertia_core::solvers::DifficultyRange;
 use inertia_core::solvers::Solution;
 use inertia_core::solvers::SolutionConvertError;
 use rand::rngs::StdRng;
+use rand::seq::IteratorRandom;
 use rand::Rng;
 use rand::SeedableRng;
 use sqlx::SqlitePool;
+use std::collections::HashSet;
 use thiserror::Error;
 
 #[derive(sqlx::FromRow, Debug)]
@@ -19,60 +22,127 @@ pub struct BoardBlobRow {
   pub solution: Vec<u8>,
 }
 
-pub fn num_db_positions_for_difficulty(difficulty: Difficulty) -> usize {
-  match difficulty {
-    Difficulty::Easiest => 1000,
-    Difficulty::Easy => 1000,
-    Difficulty::Medium => 1500,
-    Difficulty::Hard => 1500,
-    Difficulty::Hardest => 1000,
-  }
+#[derive(sqlx::FromRow, Debug)]
+struct BucketRow {
+  difficulty: i64,
+  position_count: i64,
+  distinct_ordinals: i64,
+  min_ordinal: i64,
+  max_ordinal: i64,
 }
 
-pub struct PositionDbCoordinates {
-  difficulty: Difficulty,
-  ordinal: usize,
+#[derive(Error, Debug)]
+pub enum DbBucketSizesError {
+  #[error(transparent)]
+  Db(#[from] sqlx::Error),
+  #[error("Unknown difficulty {0} in position database")]
+  UnknownDifficulty(i64),
+  #[error(
+    "Ordinals for difficulty {0:?} are not numbered from 0 without gaps or \
+     repeats"
+  )]
+  BadOrdinals(Difficulty),
+  #[error("Position database is empty")]
+  Empty,
 }
 
-pub fn get_random_db_position_coordinates_in_difficulty_range(
-  min_difficulty: Difficulty,
-  max_difficulty: Difficulty,
-) -> PositionDbCoordinates {
-  let rand = rand::thread_rng();
-  get_db_position_coordinates_in_difficulty_range_from_random(
-    rand,
-    min_difficulty,
-    max_difficulty,
+// How many positions the database holds for each difficulty. A difficulty with
+// n positions has ordinals 0 to n - 1.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DbBucketSizes([usize; 5]);
+
+impl DbBucketSizes {
+  pub fn get(&self, difficulty: Difficulty) -> usize {
+    self.0[u8::from(difficulty) as usize]
+  }
+}
+
+// Reads the bucket sizes from the database, checking that every ordinal up to
+// each size is present exactly once
+pub async fn load_db_bucket_sizes(
+  conn: &SqlitePool,
+) -> Result<DbBucketSizes, DbBucketSizesError> {
+  let rows: Vec<BucketRow> = sqlx::query_as::<_, BucketRow>(
+    "SELECT difficulty, COUNT(*) AS position_count, COUNT(DISTINCT difficulty_ordinal) AS distinct_ordinals, MIN(difficulty_ordinal) AS min_ordinal, MAX(difficulty_ordinal) AS max_ordinal FROM solved_positions GROUP BY difficulty",
   )
+  .fetch_all(conn)
+  .await?;
+
+  let mut sizes = DbBucketSizes::default();
+  for row in rows {
+    let difficulty = u8::try_from(row.difficulty)
+      .ok()
+      .and_then(|difficulty| Difficulty::try_from(difficulty).ok())
+      .ok_or(DbBucketSizesError::UnknownDifficulty(row.difficulty))?;
+    let is_contiguous = row.distinct_ordinals == row.position_count
+      && row.min_ordinal == 0
+      && row.max_ordinal == row.position_count - 1;
+    if !is_contiguous {
+      return Err(DbBucketSizesError::BadOrdinals(difficulty));
+    }
+    sizes.0[u8::from(difficulty) as usize] = row.position_count as usize;
+  }
+  if sizes.0.iter().all(|&size| size == 0) {
+    return Err(DbBucketSizesError::Empty);
+  }
+  Ok(sizes)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PositionDbCoordinates {
+  pub difficulty: Difficulty,
+  pub ordinal: usize,
 }
 
+// Picks a difficulty and then a position in it, each uniformly, rather than
+// weighting difficulties by size. This is how dailies have always been picked,
+// so a seed keeps giving the same daily.
 pub fn get_reproducible_random_db_position_coordinates_in_difficulty_range(
   seed: u64,
-  min_difficulty: Difficulty,
-  max_difficulty: Difficulty,
-) -> PositionDbCoordinates {
-  let rand = StdRng::seed_from_u64(seed as u64);
-  get_db_position_coordinates_in_difficulty_range_from_random(
-    rand,
-    min_difficulty,
-    max_difficulty,
+  bucket_sizes: DbBucketSizes,
+  range: DifficultyRange,
+) -> Option<PositionDbCoordinates> {
+  let mut rand = StdRng::seed_from_u64(seed);
+  let difficulty = Difficulty::try_from(
+    rand.gen_range(u8::from(range.min())..u8::from(range.max()) + 1),
   )
+  .expect("known range of difficulty values");
+  let bucket_size = bucket_sizes.get(difficulty);
+  (bucket_size > 0).then(|| PositionDbCoordinates {
+    difficulty,
+    ordinal: rand.gen_range(0..bucket_size),
+  })
 }
 
-pub fn get_db_position_coordinates_in_difficulty_range_from_random<T: Rng>(
+fn db_position_coordinates_in_difficulty_range(
+  bucket_sizes: DbBucketSizes,
+  range: DifficultyRange,
+) -> impl Iterator<Item = PositionDbCoordinates> {
+  range.iter().flat_map(move |difficulty| {
+    (0..bucket_sizes.get(difficulty)).map(move |ordinal| {
+      PositionDbCoordinates {
+        difficulty,
+        ordinal,
+      }
+    })
+  })
+}
+
+// Every unserved position in range is equally likely, so each difficulty is
+// weighted by how many positions it has left. Once a difficulty is used up, the
+// rest of the range is still drawn from. Returns None once everything in range
+// has been served.
+pub fn get_unserved_db_position_coordinates_in_difficulty_range_from_random<
+  T: Rng,
+>(
   mut rand: T,
-  min_difficulty: Difficulty,
-  max_difficulty: Difficulty,
-) -> PositionDbCoordinates {
-  let difficulty = Difficulty::try_from(
-    rand.gen_range(min_difficulty as u8..max_difficulty as u8),
-  )
-  .expect("guaranteed to be in range");
-  let ordinal = rand.gen_range(0..num_db_positions_for_difficulty(difficulty));
-  PositionDbCoordinates {
-    difficulty,
-    ordinal,
-  }
+  bucket_sizes: DbBucketSizes,
+  range: DifficultyRange,
+  served: &HashSet<PositionDbCoordinates>,
+) -> Option<PositionDbCoordinates> {
+  db_position_coordinates_in_difficulty_range(bucket_sizes, range)
+    .filter(|coordinates| !served.contains(coordinates))
+    .choose(&mut rand)
 }
 
 #[derive(Error, Debug)]
@@ -85,6 +155,8 @@ pub enum DbPositionFetchError {
   PositionBlobConversionError(#[from] PositionConvertError),
   #[error("Failed to convert solution blob: {0}")]
   SolutionBlobConversionError(#[from] SolutionConvertError),
+  #[error("No positions in {0:?}")]
+  NoPositionsInRangeError(DifficultyRange),
 }
 
 pub async fn get_position_from_db_coordinates(
@@ -106,3 +178,37 @@ pub async fn get_position_from_db_coordinates(
   let solution = Solution::try_from(CompressedSolution(row.solution))?;
   Ok(SolvedPosition { position, solution })
 }
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  // Picked with the selection dailies used before DifficultyRange existed,
+  // so changing these changes what players get for past and future days
+  #[test]
+  fn daily_coordinates_are_unchanged() {
+    let bucket_sizes = DbBucketSizes([1000, 1000, 1500, 1500, 1000]);
+    let range =
+      DifficultyRange::new(Difficulty::Easy, Difficulty::Medium).unwrap();
+    let expected = [
+      (20240101, Difficulty::Easy, 736),
+      (20250704, Difficulty::Easy, 487),
+      (20261019, Difficulty::Medium, 965),
+      (20261020, Difficulty::Medium, 1166),
+      (20261231, Difficulty::Medium, 286),
+    ];
+    for (seed, difficulty, ordinal) in expected {
+      assert_eq!(
+        get_reproducible_random_db_position_coordinates_in_difficulty_range(
+          seed,
+          bucket_sizes,
+          range,
+        ),
+        Some(PositionDbCoordinates {
+          difficulty,
+          ordinal
+        })
+      );
+    }
+  }
+}
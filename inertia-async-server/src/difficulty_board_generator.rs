@@ -1,47 +1,352 @@
+use std::collections::HashSet;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use inertia_core::mechanics::BoardUnavailableError;
 use inertia_core::mechanics::SolvedPosition;
 use inertia_core::mechanics::SolvedPositionGenerator;
-use inertia_core::solvers::Difficulty;
+use inertia_core::solvers::DifficultyRange;
+use rand::rngs::StdRng;
+use rand::RngCore;
+use rand::SeedableRng;
 use sqlx::SqlitePool;
+use tokio::sync::Notify;
+use tokio::task::AbortHandle;
 
 use crate::db_utils::get_position_from_db_coordinates;
-use crate::db_utils::get_random_db_position_coordinates_in_difficulty_range;
+use crate::db_utils::get_unserved_db_position_coordinates_in_difficulty_range_from_random;
+use crate::db_utils::DbBucketSizes;
+use crate::db_utils::PositionDbCoordinates;
+
+// How many positions to keep ready ahead of the next round
+const PREFETCH_COUNT: usize = 4;
+// How long to wait before querying again after the database fails
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+// How long taking a position waits for the buffer when it's empty, as it is
+// right after the generator is made
+const FILL_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Everything a room has drawn for its rounds, so that positions don't repeat
+// until the whole range has been played. Kept by the room rather than the
+// generator, so that it's still there when the generator is replaced. It isn't
+// part of room snapshots, so a restored room can repeat boards from before.
+pub type ServedPositions = Arc<Mutex<HashSet<PositionDbCoordinates>>>;
+
+type BufferedPosition = (PositionDbCoordinates, SolvedPosition);
+
+#[derive(Debug, Default)]
+struct PositionBuffer {
+  positions: Mutex<VecDeque<BufferedPosition>>,
+  filled: Condvar,
+  wanted: Notify,
+}
+
+impl PositionBuffer {
+  fn push(&self, buffered_position: BufferedPosition) {
+    self
+      .positions
+      .lock()
+      .expect("Position buffer lock poisoned")
+      .push_back(buffered_position);
+    self.filled.notify_all();
+  }
+
+  fn coordinates(&self) -> Vec<PositionDbCoordinates> {
+    self
+      .positions
+      .lock()
+      .expect("Position buffer lock poisoned")
+      .iter()
+      .map(|(coordinates, _)| *coordinates)
+      .collect()
+  }
+
+  // Blocks until there's a position or the timeout passes
+  fn pop(&self) -> Option<BufferedPosition> {
+    let positions = self
+      .positions
+      .lock()
+      .expect("Position buffer lock poisoned");
+    let (mut positions, _) = self
+      .filled
+      .wait_timeout_while(positions, FILL_TIMEOUT, |positions| {
+        positions.is_empty()
+      })
+      .expect("Position buffer lock poisoned");
+    let buffered_position = positions.pop_front();
+    drop(positions);
+    self.wanted.notify_one();
+    buffered_position
+  }
+}
+
+// Stops the prefetch task once the last generator sharing it is dropped
+#[derive(Debug)]
+struct PrefetchTask(AbortHandle);
 
+impl Drop for PrefetchTask {
+  fn drop(&mut self) {
+    self.0.abort();
+  }
+}
+
+// Serves positions in a difficulty range from the position database, without
+// repeating any in the served set. Queries run on a background task that keeps
+// a few positions buffered, so taking one rarely waits on the database. When it
+// does, such as for the first round after the generator is made, it blocks for
+// a while, so it must not be called from an async task. Positions only count as
+// served once they're taken, so the buffer is lost along with the generator
+// without using anything up. Positions are picked by the task's own rng rather
+// than the one passed in. Clones share the buffer.
 #[derive(Debug, Clone)]
 pub struct DifficultyDbBoardGenerator {
-  db_pool: SqlitePool,
-  min_difficulty: Difficulty,
-  max_difficulty: Difficulty,
+  buffer: Arc<PositionBuffer>,
+  served: ServedPositions,
+  _task: Arc<PrefetchTask>,
 }
 
 impl DifficultyDbBoardGenerator {
+  // Must be called from within the tokio runtime
   pub fn new(
     db_pool: SqlitePool,
-    min_difficulty: Difficulty,
-    max_difficulty: Difficulty,
+    bucket_sizes: DbBucketSizes,
+    difficulty_range: DifficultyRange,
+    served: ServedPositions,
   ) -> Self {
-    Self {
+    let buffer = Arc::new(PositionBuffer::default());
+    let task = tokio::spawn(prefetch_positions(
       db_pool,
-      min_difficulty,
-      max_difficulty,
+      bucket_sizes,
+      difficulty_range,
+      served.clone(),
+      buffer.clone(),
+    ));
+    Self {
+      buffer,
+      served,
+      _task: Arc::new(PrefetchTask(task.abort_handle())),
+    }
+  }
+}
+
+async fn prefetch_positions(
+  db_pool: SqlitePool,
+  bucket_sizes: DbBucketSizes,
+  difficulty_range: DifficultyRange,
+  served: ServedPositions,
+  buffer: Arc<PositionBuffer>,
+) {
+  let mut rng = StdRng::from_entropy();
+  loop {
+    let is_full = buffer
+      .positions
+      .lock()
+      .expect("Position buffer lock poisoned")
+      .len()
+      >= PREFETCH_COUNT;
+    if is_full {
+      buffer.wanted.notified().await;
+      continue;
+    }
+
+    // Positions already waiting in the buffer are skipped along with the
+    // served ones
+    let (coordinates, is_buffered) = {
+      let mut served = served.lock().expect("Served positions lock poisoned");
+      let buffered = buffer.coordinates();
+      let mut skipped = served.clone();
+      skipped.extend(buffered.iter().copied());
+      let coordinates =
+        get_unserved_db_position_coordinates_in_difficulty_range_from_random(
+          &mut rng,
+          bucket_sizes,
+          difficulty_range,
+          &skipped,
+        );
+      if coordinates.is_none() && !served.is_empty() {
+        tracing::debug!("Every position in range served, starting over");
+        served.clear();
+        continue;
+      }
+      (coordinates, !buffered.is_empty())
+    };
+    let Some(coordinates) = coordinates else {
+      if is_buffered {
+        // The whole range is in the buffer, so there's nothing more to fetch
+        // until some of it is taken
+        buffer.wanted.notified().await;
+        continue;
+      }
+      tracing::error!("No positions in {:?}", difficulty_range);
+      return;
+    };
+    match get_position_from_db_coordinates(&db_pool, coordinates).await {
+      Ok(solved_position) => {
+        buffer.push((coordinates, solved_position));
+      }
+      Err(err) => {
+        tracing::error!("Error fetching solved position: {}", err);
+        tokio::time::sleep(RETRY_DELAY).await;
+      }
     }
   }
 }
 
 impl SolvedPositionGenerator for DifficultyDbBoardGenerator {
-  fn generate_solved_position(&self) -> SolvedPosition {
-    futures::executor::block_on(async {
-      get_position_from_db_coordinates(
-        &self.db_pool,
-        get_random_db_position_coordinates_in_difficulty_range(
-          self.min_difficulty,
-          self.max_difficulty,
-        ),
+  fn generate_solved_position_with_rng(
+    &self,
+    rng: &mut dyn RngCore,
+  ) -> SolvedPosition {
+    self
+      .try_generate_solved_position_with_rng(rng)
+      .expect("No position from the database in time")
+  }
+
+  fn try_generate_solved_position_with_rng(
+    &self,
+    _rng: &mut dyn RngCore,
+  ) -> Result<SolvedPosition, BoardUnavailableError> {
+    let (coordinates, solved_position) =
+      self.buffer.pop().ok_or(BoardUnavailableError)?;
+    self
+      .served
+      .lock()
+      .expect("Served positions lock poisoned")
+      .insert(coordinates);
+    Ok(solved_position)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use inertia_core::board_generators::OneMoveSolutionBoardGenerator;
+  use inertia_core::mechanics::CompressedPosition;
+  use inertia_core::solvers::CompressedSolution;
+  use inertia_core::solvers::Difficulty;
+  use sqlx::sqlite::SqlitePoolOptions;
+
+  use crate::db_utils::load_db_bucket_sizes;
+
+  const POSITION_COUNT: usize = 10;
+
+  // An in memory database of easy positions, one connection so that every
+  // query sees the same database
+  async fn position_db() -> (SqlitePool, Vec<SolvedPosition>) {
+    let db_pool = SqlitePoolOptions::new()
+      .max_connections(1)
+      .connect("sqlite::memory:")
+      .await
+      .unwrap();
+    sqlx::query(
+      "CREATE TABLE solved_positions (difficulty INTEGER, difficulty_ordinal INTEGER, position BLOB, solution BLOB)",
+    )
+    .execute(&db_pool)
+    .await
+    .unwrap();
+    let generator = OneMoveSolutionBoardGenerator::new();
+    let mut positions = Vec::new();
+    for ordinal in 0..POSITION_COUNT {
+      let solved_position = generator.generate_solved_position_with_rng(
+        &mut StdRng::seed_from_u64(ordinal as u64),
+      );
+      sqlx::query(
+        "INSERT INTO solved_positions (difficulty, difficulty_ordinal, position, solution) VALUES (?, ?, ?, ?)",
       )
+      .bind(u8::from(Difficulty::Easy))
+      .bind(ordinal as u32)
+      .bind(CompressedPosition::from(&solved_position.position).0)
+      .bind(CompressedSolution::from(&solved_position.solution).0)
+      .execute(&db_pool)
       .await
-      .unwrap_or_else(|err| {
-        tracing::error!("Error fetching solved position: {}", err);
-        return SolvedPosition::default();
-      })
+      .unwrap();
+      positions.push(solved_position);
+    }
+    (db_pool, positions)
+  }
+
+  #[tokio::test]
+  async fn skips_positions_served_by_earlier_generators() {
+    let (db_pool, positions) = position_db().await;
+    let bucket_sizes = load_db_bucket_sizes(&db_pool).await.unwrap();
+    let unserved = 7;
+    let served = ServedPositions::default();
+    served.lock().unwrap().extend(
+      (0..POSITION_COUNT)
+        .filter(|&ordinal| ordinal != unserved)
+        .map(|ordinal| PositionDbCoordinates {
+          difficulty: Difficulty::Easy,
+          ordinal,
+        }),
+    );
+    let generator = DifficultyDbBoardGenerator::new(
+      db_pool,
+      bucket_sizes,
+      DifficultyRange::new(Difficulty::Easy, Difficulty::Easy).unwrap(),
+      served,
+    );
+    let result = tokio::task::spawn_blocking(move || {
+      generator.try_generate_solved_position()
+    })
+    .await
+    .unwrap()
+    .unwrap();
+    assert_eq!(result.position, positions[unserved].position);
+  }
+
+  #[tokio::test]
+  async fn only_taken_positions_are_served() {
+    let (db_pool, _) = position_db().await;
+    let bucket_sizes = load_db_bucket_sizes(&db_pool).await.unwrap();
+    let served = ServedPositions::default();
+    let generator = DifficultyDbBoardGenerator::new(
+      db_pool,
+      bucket_sizes,
+      DifficultyRange::new(Difficulty::Easy, Difficulty::Easy).unwrap(),
+      served.clone(),
+    );
+    let drawing_generator = generator.clone();
+    tokio::task::spawn_blocking(move || {
+      drawing_generator.try_generate_solved_position()
+    })
+    .await
+    .unwrap()
+    .unwrap();
+    // Let the buffer fill back up
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(generator.buffer.coordinates().len(), PREFETCH_COUNT);
+    assert_eq!(served.lock().unwrap().len(), 1);
+  }
+
+  #[tokio::test]
+  async fn waits_for_first_position() {
+    let buffer = Arc::new(PositionBuffer::default());
+    let task = tokio::spawn({
+      let buffer = buffer.clone();
+      async move {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        buffer.push((
+          PositionDbCoordinates {
+            difficulty: Difficulty::Easy,
+            ordinal: 0,
+          },
+          SolvedPosition::default(),
+        ));
+      }
+    });
+    let generator = DifficultyDbBoardGenerator {
+      buffer,
+      served: ServedPositions::default(),
+      _task: Arc::new(PrefetchTask(task.abort_handle())),
+    };
+    let result = tokio::task::spawn_blocking(move || {
+      generator.try_generate_solved_position()
     })
+    .await
+    .unwrap();
+    assert!(result.is_ok());
   }
 }
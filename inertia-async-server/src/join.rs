@@ -2,8 +2,12 @@ use std::net::SocketAddr;
 
 use axum::extract::ws::Message;
 use axum::extract::ws::WebSocket;
+use futures::stream::SplitSink;
 use futures::stream::SplitStream;
+use futures::SinkExt;
 use futures::StreamExt;
+use inertia_core::board_generators::GeneratorSpec;
+use inertia_core::message::ErrorMessage;
 use inertia_core::message::FromClientMessage;
 use inertia_core::message::JoinMessage;
 use inertia_core::message::ToClientMessage;
@@ -16,8 +20,8 @@ use inertia_core::state::event::connect::Connect;
 use thiserror::Error;
 use tokio::sync::broadcast;
 
-use crate::difficulty_board_generator::DifficultyDbBoardGenerator;
 use crate::state::AppState;
+use crate::state::RoomBroadcast;
 
 #[derive(Debug, Error)]
 pub enum JoinError {
@@ -33,10 +37,30 @@ pub struct JoinInfo {
   pub room_id: RoomId,
   pub player_id: PlayerId,
   pub player_name: PlayerName,
-  pub broadcast_channel_receiver: broadcast::Receiver<ToClientMessage>,
+  pub broadcast_channel_receiver: broadcast::Receiver<RoomBroadcast>,
+}
+
+// Lets the client know why its join was refused. Failing to send is not
+// worth more than a log, since the client may be gone already.
+async fn send_join_error(
+  ws_sender: &mut SplitSink<WebSocket, Message>,
+  message: String,
+) {
+  let msg = ToClientMessage::Error(ErrorMessage { message });
+  let result = match serde_json::to_string(&msg) {
+    Ok(msg_json) => ws_sender
+      .send(Message::Text(msg_json.into()))
+      .await
+      .map_err(|err| err.to_string()),
+    Err(err) => Err(err.to_string()),
+  };
+  if let Err(err) = result {
+    tracing::debug!("Failed to send join error: {}", err);
+  }
 }
 
 pub async fn join(
+  ws_sender: &mut SplitSink<WebSocket, Message>,
   ws_receiver: &mut SplitStream<WebSocket>,
   socket_address: &SocketAddr,
   state: &AppState,
@@ -88,27 +112,42 @@ pub async fn join(
       room_id,
       min_difficulty,
       max_difficulty,
+      generator,
+      timers,
+      scoring,
+      game_length,
+      role,
     } = join_message;
 
-    state
-      .ensure_room_exists(
-        room_id,
-        DifficultyDbBoardGenerator::new(
-          state.db_pool.clone(),
-          min_difficulty.unwrap_or(Difficulty::Easiest),
-          max_difficulty.unwrap_or(Difficulty::Hard),
-        ),
-      )
-      .await;
+    let generator_spec =
+      generator.unwrap_or_else(|| GeneratorSpec::DbByDifficulty {
+        min_difficulty: min_difficulty.unwrap_or(Difficulty::Easiest),
+        max_difficulty: max_difficulty.unwrap_or(Difficulty::Hard),
+      });
 
     tracing::debug!(
-      "WebSocket [{}]: Joining room {:?} with difficulty {:?} -> {:?}",
+      "WebSocket [{}]: Joining room {:?} with generator {:?}",
       socket_address,
       room_id,
-      min_difficulty,
-      max_difficulty
+      generator_spec
     );
 
+    let timers = timers.unwrap_or_default();
+    if let Err(err) = state
+      .ensure_room_exists(
+        room_id,
+        generator_spec,
+        timers,
+        scoring.unwrap_or_default(),
+        game_length.unwrap_or_default(),
+      )
+      .await
+    {
+      reject!("Unable to create room: {}", err);
+      send_join_error(ws_sender, err.to_string()).await;
+      continue;
+    }
+
     let broadcast_channel_receiver =
       match state.get_broadcast_channel_pair(room_id).await {
         Ok(result) => result,
@@ -122,6 +161,7 @@ pub async fn join(
       player_name: player_name.clone(),
       player_id,
       player_reconnect_key,
+      role: role.unwrap_or_default(),
     });
     if let Err(err) = state.apply_event(room_id, connect_event).await {
       reject!("Error during connection: {:?}", err);
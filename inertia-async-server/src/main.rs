@@ -1,12 +1,16 @@
 mod db_utils;
 mod difficulty_board_generator;
 mod join;
+mod snapshots;
 mod state;
 mod ws_receiver;
 
 use std::collections::HashMap;
+use std::future;
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
 use axum::extract::ws;
 use axum::extract::ws::WebSocket;
@@ -25,17 +29,21 @@ use chrono::Utc;
 use chrono_tz::US;
 use db_utils::get_position_from_db_coordinates;
 use db_utils::get_reproducible_random_db_position_coordinates_in_difficulty_range;
+use db_utils::load_db_bucket_sizes;
+use db_utils::DbBucketSizes;
 use db_utils::DbPositionFetchError;
 use futures::SinkExt;
 use futures::StreamExt;
 use inertia_core::mechanics::B64EncodedCompressedPosition;
 use inertia_core::mechanics::CompressedPosition;
 use inertia_core::mechanics::SolvedPosition;
+use inertia_core::message::ErrorMessage;
 use inertia_core::message::FromClientMessage;
 use inertia_core::message::ToClientMessage;
 use inertia_core::solvers::B64EncodedCompressedSolution;
 use inertia_core::solvers::CompressedSolution;
 use inertia_core::solvers::Difficulty;
+use inertia_core::solvers::DifficultyRange;
 use inertia_core::solvers::Solution;
 use inertia_core::state::event::apply_event::RoomEvent;
 use inertia_core::state::event::disconnect::Disconnect;
@@ -44,8 +52,13 @@ use serde_json::Value;
 use sqlx::sqlite::SqlitePoolOptions;
 use sqlx::SqlitePool;
 use tokio::net::TcpListener;
+#[cfg(unix)]
+use tokio::signal::unix::signal;
+#[cfg(unix)]
+use tokio::signal::unix::SignalKind;
 use tokio::sync::broadcast;
 use tokio::sync::mpsc;
+use tokio::sync::oneshot;
 use tokio::sync::RwLock;
 use tower_http::cors::CorsLayer;
 use tracing_subscriber::layer::SubscriberExt;
@@ -54,10 +67,19 @@ use tracing_subscriber::util::SubscriberInitExt;
 use crate::join::join;
 use crate::join::JoinInfo;
 
+use crate::snapshots::restore_rooms;
+use crate::snapshots::save_rooms;
+use crate::snapshots::save_rooms_periodically;
 use crate::state::AppState;
+use crate::state::RoomBroadcast;
 use crate::ws_receiver::handle_message_from_client;
 
 const DB_URL: &str = "sqlite:db/positions.db?mode=ro";
+// Where open rooms are saved while running and on shutdown, and restored from
+// on start
+const SNAPSHOT_PATH: &str = "db/rooms.json";
+// How long a closing socket gets to send what's already queued for it
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(1);
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -74,11 +96,30 @@ async fn main() -> anyhow::Result<()> {
     .connect(&std::env::var("DB_URL").unwrap_or(DB_URL.to_string()))
     .await?;
 
+  let db_bucket_sizes = load_db_bucket_sizes(&db_pool).await?;
+  tracing::info!("Positions per difficulty: {:?}", db_bucket_sizes);
+
   let app_state = AppState {
     db_pool,
+    db_bucket_sizes,
     rooms: Arc::new(RwLock::new(HashMap::new())),
   };
 
+  let snapshot_path = PathBuf::from(
+    std::env::var("SNAPSHOT_PATH").unwrap_or(SNAPSHOT_PATH.to_string()),
+  );
+  match restore_rooms(&app_state, &snapshot_path).await {
+    Ok(count) => tracing::info!("Restored {} rooms", count),
+    Err(err) => tracing::error!("Error restoring rooms: {}", err),
+  }
+  let shutdown_state = app_state.clone();
+  let (stop_saving, saving_stopped) = oneshot::channel();
+  let periodic_save = tokio::spawn(save_rooms_periodically(
+    app_state.clone(),
+    snapshot_path.clone(),
+    saving_stopped,
+  ));
+
   let app = Router::new()
     .route("/healthcheck", get(healthcheck))
     .route("/status", get(status))
@@ -92,10 +133,46 @@ async fn main() -> anyhow::Result<()> {
   let address = SocketAddr::from(([0, 0, 0, 0], 8001));
   let listener = TcpListener::bind(&address).await?;
   tracing::info!("Listening on {}", address);
-  axum::serve(listener, app).await?;
+  axum::serve(listener, app)
+    .with_graceful_shutdown(shutdown_signal())
+    .await?;
+
+  stop_saving.send(()).ok();
+  periodic_save.await.ok();
+  match save_rooms(&shutdown_state, &snapshot_path).await {
+    Ok(count) => tracing::info!("Saved {} rooms", count),
+    Err(err) => tracing::error!("Error saving rooms: {}", err),
+  }
   Ok(())
 }
 
+// Containers are stopped with SIGTERM, while ctrl-c sends SIGINT
+async fn shutdown_signal() {
+  let interrupt = async {
+    tokio::signal::ctrl_c().await.ok();
+  };
+  #[cfg(unix)]
+  let terminate = async {
+    match signal(SignalKind::terminate()) {
+      Ok(mut terminate) => {
+        terminate.recv().await;
+      }
+      Err(err) => {
+        tracing::error!("Unable to listen for SIGTERM: {}", err);
+        future::pending::<()>().await;
+      }
+    }
+  };
+  #[cfg(not(unix))]
+  let terminate = future::pending::<()>();
+
+  tokio::select! {
+    _ = interrupt => {},
+    _ = terminate => {},
+  }
+  tracing::info!("Shutting down");
+}
+
 async fn ws_handler(
   ws: WebSocketUpgrade,
   State(state): State<AppState>,
@@ -120,21 +197,24 @@ fn get_canonical_date() -> NaiveDate {
 
 async fn get_daily_solved_position(
   db_pool: &SqlitePool,
+  bucket_sizes: DbBucketSizes,
 ) -> Result<SolvedPosition, DbPositionFetchError> {
   let today_date = get_canonical_date();
   let seed = today_date.year() as u64 * 10000
     + today_date.month() as u64 * 100
     + today_date.day() as u64;
   tracing::info!("Fetching daily with seed: {}", seed);
-  get_position_from_db_coordinates(
-    db_pool,
+  // Same difficulties the daily has always been drawn from
+  let range = DifficultyRange::new(Difficulty::Easy, Difficulty::Medium)
+    .expect("Daily difficulty range is valid");
+  let coordinates =
     get_reproducible_random_db_position_coordinates_in_difficulty_range(
       seed,
-      Difficulty::Easy,
-      Difficulty::Hard,
-    ),
-  )
-  .await
+      bucket_sizes,
+      range,
+    )
+    .ok_or(DbPositionFetchError::NoPositionsInRangeError(range))?;
+  get_position_from_db_coordinates(db_pool, coordinates).await
 }
 
 async fn check_daily(
@@ -146,7 +226,7 @@ async fn check_daily(
       .map_err(|_err| StatusCode::BAD_REQUEST)?,
   )
   .map_err(|_err| StatusCode::BAD_REQUEST)?;
-  get_daily_solved_position(&state.db_pool)
+  get_daily_solved_position(&state.db_pool, state.db_bucket_sizes)
     .await
     .map(|solved_position| solved_position.check_solution(&solution))
     .map(|result| json!({ "result": result }))
@@ -160,7 +240,7 @@ async fn check_daily(
 async fn daily(
   State(state): State<AppState>,
 ) -> Result<Json<Value>, StatusCode> {
-  get_daily_solved_position(&state.db_pool)
+  get_daily_solved_position(&state.db_pool, state.db_bucket_sizes)
     .await
     .map(|solved_position| {
       B64EncodedCompressedPosition::from(CompressedPosition::from(
@@ -197,7 +277,9 @@ async fn handle_socket(
     player_name,
     mut broadcast_channel_receiver,
     ..
-  } = match join(&mut ws_receiver, &socket_address, &state).await {
+  } = match join(&mut ws_sender, &mut ws_receiver, &socket_address, &state)
+    .await
+  {
     Ok(info) => info,
     Err(err) => {
       ws_debug!("Failed to connect: {}", err);
@@ -241,6 +323,9 @@ async fn handle_socket(
 
   let individual_sender_for_task = individual_channel_sender.clone();
   let mut broadcast_channel_receive_task = tokio::spawn(async move {
+    // Updates can arrive from before the player joined, so they only count as
+    // kicked once they've been seen in the room and then drop out of it
+    let mut seen_in_room = false;
     loop {
       let channel_msg = broadcast_channel_receiver.recv().await;
       let channel_msg = match channel_msg {
@@ -256,10 +341,31 @@ async fn handle_socket(
           }
         },
       };
-      if individual_sender_for_task.send(channel_msg).await.is_err() {
+      let (msg, in_room) = match channel_msg {
+        RoomBroadcast::RoomUpdate(room_state) => (
+          ToClientMessage::RoomUpdate(Box::new(room_state.view(player_id))),
+          room_state
+            .get_meta()
+            .map(|meta| meta.get_role(player_id).is_some()),
+        ),
+        RoomBroadcast::Message(msg) => (msg, None),
+      };
+      if individual_sender_for_task.send(msg).await.is_err() {
         ws_debug!("Failed to forward message to individual channel");
         break;
       }
+      match in_room {
+        Some(true) => seen_in_room = true,
+        Some(false) if seen_in_room => {
+          ws_debug!("Removed from room");
+          let msg = ToClientMessage::Error(ErrorMessage {
+            message: String::from("You were removed from the room"),
+          });
+          individual_sender_for_task.send(msg).await.ok();
+          break;
+        }
+        _ => {}
+      }
     }
   });
 
@@ -321,8 +427,14 @@ async fn handle_socket(
       ws_receive_task.abort();
     }
     _ = (&mut broadcast_channel_receive_task) => {
-      individual_channel_receive_task.abort();
       ws_receive_task.abort();
+      // Closing the channel lets the individual task send whatever is left,
+      // like the reason for being removed, before it finishes
+      drop(individual_channel_sender);
+      tokio::time::timeout(FLUSH_TIMEOUT, &mut individual_channel_receive_task)
+        .await
+        .ok();
+      individual_channel_receive_task.abort();
     },
     _ = (&mut ws_receive_task) => {
       individual_channel_receive_task.abort();
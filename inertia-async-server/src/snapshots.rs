@@ -0,0 +1,183 @@
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use inertia_core::state::snapshot::RoomSnapshot;
+use thiserror::Error;
+use tokio::sync::oneshot;
+use tokio::time::Instant;
+
+use crate::state::AppState;
+
+// How often open rooms are saved while the server runs, which is at most how
+// much play a crash loses
+const SAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Error, Debug)]
+pub enum SnapshotFileError {
+  #[error(transparent)]
+  Io(#[from] io::Error),
+  #[error(transparent)]
+  Json(#[from] serde_json::Error),
+}
+
+// Rooms that couldn't be restored are kept here, next to the snapshot file
+fn failed_snapshot_path(path: &Path) -> PathBuf {
+  path.with_extension("failed.json")
+}
+
+// Written to a temporary file first, so that a crash partway through leaves
+// the previous file as it was
+async fn write_snapshots(
+  path: &Path,
+  snapshots: &[RoomSnapshot],
+) -> Result<(), SnapshotFileError> {
+  if let Some(parent) = path.parent() {
+    tokio::fs::create_dir_all(parent).await?;
+  }
+  let temp_path = path.with_extension("tmp");
+  tokio::fs::write(&temp_path, serde_json::to_vec(snapshots)?).await?;
+  tokio::fs::rename(&temp_path, path).await?;
+  Ok(())
+}
+
+// Writes every open room to the file, so they can be restored on the next
+// start
+pub async fn save_rooms(
+  state: &AppState,
+  path: &Path,
+) -> Result<usize, SnapshotFileError> {
+  let snapshots = state.snapshot_rooms().await;
+  write_snapshots(path, &snapshots).await?;
+  Ok(snapshots.len())
+}
+
+// Saves the open rooms every so often until told to stop. A save in progress
+// is finished first, so that it can't land after the one made on shutdown.
+pub async fn save_rooms_periodically(
+  state: AppState,
+  path: PathBuf,
+  mut stop: oneshot::Receiver<()>,
+) {
+  let mut interval =
+    tokio::time::interval_at(Instant::now() + SAVE_INTERVAL, SAVE_INTERVAL);
+  loop {
+    tokio::select! {
+      _ = interval.tick() => {}
+      _ = &mut stop => return,
+    }
+    match save_rooms(&state, &path).await {
+      Ok(count) => tracing::debug!("Saved {} rooms", count),
+      Err(err) => tracing::error!("Error saving rooms: {}", err),
+    }
+  }
+}
+
+// Restores the rooms saved in the file, if there is one. The file is removed
+// afterwards so the same rooms aren't restored twice. Rooms that couldn't be
+// restored are moved to a file of their own rather than lost, since the
+// snapshot file is overwritten the next time rooms are saved.
+pub async fn restore_rooms(
+  state: &AppState,
+  path: &Path,
+) -> Result<usize, SnapshotFileError> {
+  let contents = match tokio::fs::read(path).await {
+    Ok(contents) => contents,
+    Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(0),
+    Err(error) => return Err(error.into()),
+  };
+  let snapshots: Vec<RoomSnapshot> = serde_json::from_slice(&contents)?;
+  let mut restored = 0;
+  let mut failed = Vec::new();
+  for snapshot in snapshots {
+    match state.restore_room(snapshot.clone()).await {
+      Ok(()) => restored += 1,
+      Err(error) => {
+        tracing::error!("Unable to restore room: {}", error);
+        failed.push(snapshot);
+      }
+    }
+  }
+  if !failed.is_empty() {
+    let failed_path = failed_snapshot_path(path);
+    write_snapshots(&failed_path, &failed).await?;
+    tracing::warn!(
+      "Kept {} rooms that couldn't be restored in {}",
+      failed.len(),
+      failed_path.display()
+    );
+  }
+  tokio::fs::remove_file(path).await?;
+  Ok(restored)
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use std::collections::HashMap;
+  use std::sync::Arc;
+
+  use inertia_core::board_generators::GeneratorSpec;
+  use inertia_core::state::data::GameLength;
+  use inertia_core::state::data::RoomId;
+  use inertia_core::state::data::RoundTimers;
+  use inertia_core::state::scoring::ScoringPolicy;
+  use inertia_core::state::snapshot::RoomStateSnapshot;
+  use inertia_core::state::snapshot::SNAPSHOT_VERSION;
+  use sqlx::sqlite::SqlitePoolOptions;
+  use tokio::sync::RwLock;
+
+  use crate::db_utils::DbBucketSizes;
+
+  fn app_state() -> AppState {
+    AppState {
+      db_pool: SqlitePoolOptions::new()
+        .connect_lazy("sqlite::memory:")
+        .unwrap(),
+      db_bucket_sizes: DbBucketSizes::default(),
+      rooms: Arc::new(RwLock::new(HashMap::new())),
+    }
+  }
+
+  #[tokio::test]
+  async fn keeps_rooms_that_failed_to_restore() {
+    let directory = std::env::temp_dir()
+      .join(format!("inertia-snapshots-{}", std::process::id()));
+    let path = directory.join("rooms.json");
+
+    let state = app_state();
+    state
+      .ensure_room_exists(
+        RoomId(0),
+        GeneratorSpec::OneMove,
+        RoundTimers::default(),
+        ScoringPolicy::default(),
+        GameLength::default(),
+      )
+      .await
+      .unwrap();
+    let mut snapshots = state.snapshot_rooms().await;
+    snapshots.push(RoomSnapshot {
+      version: SNAPSHOT_VERSION,
+      state: RoomStateSnapshot::None,
+    });
+    write_snapshots(&path, &snapshots).await.unwrap();
+
+    let restored = restore_rooms(&app_state(), &path).await.unwrap();
+    assert_eq!(restored, 1);
+    assert!(!path.exists());
+    let failed: Vec<RoomSnapshot> = serde_json::from_slice(
+      &tokio::fs::read(failed_snapshot_path(&path)).await.unwrap(),
+    )
+    .unwrap();
+    assert!(matches!(
+      failed.as_slice(),
+      [RoomSnapshot {
+        state: RoomStateSnapshot::None,
+        ..
+      }]
+    ));
+    tokio::fs::remove_dir_all(directory).await.unwrap();
+  }
+}
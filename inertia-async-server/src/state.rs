@@ -1,11 +1,29 @@
+use chrono::Utc;
+use inertia_core::board_generators::GeneratorRegistry;
+use inertia_core::board_generators::GeneratorSpec;
+use inertia_core::board_generators::GeneratorSpecError;
+use inertia_core::mechanics::BoardUnavailableError;
 use inertia_core::mechanics::SolvedPositionGenerator;
 use inertia_core::message::CountdownUpdateMessage;
 use inertia_core::message::ToClientMessage;
+use inertia_core::state::data::GameLength;
+use inertia_core::state::data::GameLengthError;
+use inertia_core::state::data::PlayerId;
 use inertia_core::state::data::RoomId;
 use inertia_core::state::data::RoomState;
+use inertia_core::state::data::RoundStart;
+use inertia_core::state::data::RoundSummary;
+use inertia_core::state::data::RoundTimerError;
+use inertia_core::state::data::RoundTimers;
 use inertia_core::state::event::apply_event::RoomEvent;
 use inertia_core::state::event::result::EventError;
 use inertia_core::state::event::result::EventResult;
+use inertia_core::state::event::start_round::StartRound;
+use inertia_core::state::event::update_settings::UpdateSettings;
+use inertia_core::state::event_log::EventLog;
+use inertia_core::state::scoring::ScoringPolicy;
+use inertia_core::state::snapshot::RoomSnapshot;
+use inertia_core::state::snapshot::SnapshotVersionError;
 use sqlx::SqlitePool;
 use std::collections::HashMap;
 use std::mem;
@@ -13,19 +31,51 @@ use std::sync::Arc;
 use std::time::Duration;
 use thiserror::Error;
 use tokio::sync::broadcast;
+use tokio::sync::Mutex;
 use tokio::sync::RwLock;
 use tokio::task::JoinHandle;
 use tokio::time::Instant;
 
+use crate::db_utils::DbBucketSizes;
+use crate::difficulty_board_generator::DifficultyDbBoardGenerator;
+use crate::difficulty_board_generator::ServedPositions;
+
+// How long a restored room stays open for its players to come back
+const RESTORED_ROOM_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+// How long to wait for a generator to be built before giving up on the spec.
+// Solving is bounded too, so the build itself also stops before long.
+const GENERATOR_BUILD_TIMEOUT: Duration = Duration::from_secs(20);
+
 pub struct Countdown {
   pub task: JoinHandle<()>,
   pub stop: Instant,
 }
 
+// Sent to every connection in a room. Room updates are turned into each
+// connection's own view of the room before they go out.
+#[derive(Clone, Debug)]
+pub enum RoomBroadcast {
+  RoomUpdate(Arc<RoomState>),
+  Message(ToClientMessage),
+}
+
 pub struct RoomUtils {
   pub room_id: RoomId,
-  pub broadcast_channel: broadcast::Sender<ToClientMessage>,
+  pub broadcast_channel: broadcast::Sender<RoomBroadcast>,
   pub countdown: Option<Countdown>,
+  // Ends a timed game once its time is up
+  pub game_timer: Option<JoinHandle<()>>,
+  // Closes a restored room if nobody comes back to it
+  pub restore_timeout: Option<JoinHandle<()>>,
+  // Draws the boards for new rounds, following the room's generator spec
+  pub generator: Box<dyn SolvedPositionGenerator>,
+  // Database positions the room has been given, across every generator it has
+  pub served_positions: ServedPositions,
+  // Held while a board is drawn or the generator replaced. Both happen off
+  // the room lock, since they can take a while, and this keeps them in order.
+  pub generator_lock: Arc<Mutex<()>>,
+  // Every event applied to the room, enough to rebuild its state
+  pub event_log: EventLog,
 }
 
 pub struct Room {
@@ -34,17 +84,39 @@ pub struct Room {
 }
 
 impl Room {
-  pub fn new<G: SolvedPositionGenerator + 'static>(
+  pub fn new(
     room_id: RoomId,
-    generator: G,
+    generator_spec: GeneratorSpec,
+    generator: Box<dyn SolvedPositionGenerator>,
+    served_positions: ServedPositions,
+    timers: RoundTimers,
+    scoring: ScoringPolicy,
+    game_length: GameLength,
+  ) -> Self {
+    let state =
+      RoomState::initial(room_id, generator_spec, timers, scoring, game_length);
+    Room::from_state(room_id, state, generator, served_positions)
+  }
+
+  pub fn from_state(
+    room_id: RoomId,
+    state: RoomState,
+    generator: Box<dyn SolvedPositionGenerator>,
+    served_positions: ServedPositions,
   ) -> Self {
     Room {
       utils: RoomUtils {
         room_id,
         broadcast_channel: broadcast::channel(16).0,
         countdown: None,
+        game_timer: None,
+        restore_timeout: None,
+        generator,
+        served_positions,
+        generator_lock: Arc::new(Mutex::new(())),
+        event_log: EventLog::new(state.clone()),
       },
-      state: RoomState::initial(room_id, generator),
+      state,
     }
   }
 }
@@ -52,6 +124,7 @@ impl Room {
 #[derive(Clone)]
 pub struct AppState {
   pub db_pool: SqlitePool,
+  pub db_bucket_sizes: DbBucketSizes,
   pub rooms: Arc<RwLock<HashMap<RoomId, RwLock<Room>>>>,
 }
 
@@ -62,7 +135,7 @@ pub enum BroadcastError {
   #[error("No countdown exists")]
   NoCountdownExists,
   #[error(transparent)]
-  SendFailed(broadcast::error::SendError<ToClientMessage>),
+  SendFailed(broadcast::error::SendError<RoomBroadcast>),
 }
 
 #[derive(Error, Debug)]
@@ -70,9 +143,47 @@ pub enum ApplyEventError {
   #[error(transparent)]
   NoRoomExists(#[from] NoRoomExistsError),
   #[error(transparent)]
-  ApplyRoomEventError(#[from] EventError),
+  // Boxed since events, which errors carry, can be large
+  ApplyRoomEventError(#[from] Box<EventError>),
   #[error("Validation for event failed.")]
   ValidationFailedError,
+  #[error(transparent)]
+  GeneratorSpec(#[from] GeneratorSpecError),
+  #[error(transparent)]
+  BoardUnavailable(#[from] BoardUnavailableError),
+}
+
+#[derive(Error, Debug)]
+pub enum CreateRoomError {
+  #[error(transparent)]
+  Generator(#[from] GeneratorSpecError),
+  #[error(transparent)]
+  Timers(#[from] RoundTimerError),
+  #[error(transparent)]
+  GameLength(#[from] GameLengthError),
+}
+
+// Used to note when rounds enter each phase
+fn now_millis() -> u64 {
+  u64::try_from(Utc::now().timestamp_millis()).unwrap_or_default()
+}
+
+#[derive(Error, Debug)]
+pub enum RestoreRoomError {
+  #[error(transparent)]
+  UnsupportedVersion(#[from] SnapshotVersionError),
+  #[error("Snapshot has no room in it")]
+  NoRoom,
+  #[error(transparent)]
+  Generator(#[from] GeneratorSpecError),
+}
+
+// Rounds are started and settings changed by the host between rounds
+fn is_host_between_rounds(state: &RoomState, player_id: PlayerId) -> bool {
+  matches!(
+    state,
+    RoomState::RoundSummary(RoundSummary { meta, .. }) if meta.is_host(player_id)
+  )
 }
 
 #[derive(Error, Debug)]
@@ -113,19 +224,153 @@ impl AppState {
     f(&mut room)
   }
 
-  pub async fn ensure_room_exists<G: SolvedPositionGenerator + 'static>(
+  // Database generators are made per room, so they share the room's served
+  // positions
+  fn generator_registry(
+    &self,
+    served_positions: ServedPositions,
+  ) -> GeneratorRegistry {
+    let db_pool = self.db_pool.clone();
+    let db_bucket_sizes = self.db_bucket_sizes;
+    GeneratorRegistry::new().with_db_generator(move |difficulty_range| {
+      Box::new(DifficultyDbBoardGenerator::new(
+        db_pool.clone(),
+        db_bucket_sizes,
+        difficulty_range,
+        served_positions.clone(),
+      ))
+    })
+  }
+
+  // Building a generator can mean solving every position in a list, so it's
+  // done on a blocking thread rather than holding up other rooms
+  async fn build_generator(
+    &self,
+    generator_spec: GeneratorSpec,
+    served_positions: ServedPositions,
+  ) -> Result<Box<dyn SolvedPositionGenerator>, GeneratorSpecError> {
+    let registry = self.generator_registry(served_positions);
+    let build =
+      tokio::task::spawn_blocking(move || registry.build(&generator_spec));
+    tokio::time::timeout(GENERATOR_BUILD_TIMEOUT, build)
+      .await
+      .map_err(|_| GeneratorSpecError::BuildTimedOut)?
+      .expect("Generator build task failed")
+  }
+
+  async fn generator_lock(
     &self,
     room_id: RoomId,
-    generator: G,
-  ) {
+  ) -> Result<Arc<Mutex<()>>, NoRoomExistsError> {
+    self
+      .with_room_read(room_id, |room| Ok(room.utils.generator_lock.clone()))
+      .await
+  }
+
+  // The settings are only used if the room has to be created
+  pub async fn ensure_room_exists(
+    &self,
+    room_id: RoomId,
+    generator_spec: GeneratorSpec,
+    timers: RoundTimers,
+    scoring: ScoringPolicy,
+    game_length: GameLength,
+  ) -> Result<(), CreateRoomError> {
     let should_create_room = self.rooms.read().await.get(&room_id).is_none();
     if should_create_room {
-      self
-        .rooms
-        .write()
-        .await
-        .entry(room_id)
-        .or_insert_with(|| RwLock::new(Room::new(room_id, generator)));
+      timers.validate()?;
+      game_length.validate()?;
+      let served_positions = ServedPositions::default();
+      let generator = self
+        .build_generator(generator_spec.clone(), served_positions.clone())
+        .await?;
+      self.rooms.write().await.entry(room_id).or_insert_with(|| {
+        RwLock::new(Room::new(
+          room_id,
+          generator_spec,
+          generator,
+          served_positions,
+          timers,
+          scoring,
+          game_length,
+        ))
+      });
+    }
+    Ok(())
+  }
+
+  // Full snapshots of every open room, for restoring them after a restart
+  pub async fn snapshot_rooms(&self) -> Vec<RoomSnapshot> {
+    let rooms = self.rooms.read().await;
+    let mut snapshots = Vec::with_capacity(rooms.len());
+    for room in rooms.values() {
+      let state = room.read().await.state.clone();
+      if state.get_meta().is_some() {
+        snapshots.push(RoomSnapshot::from(state));
+      }
+    }
+    snapshots
+  }
+
+  // Nobody is connected to a restored room until they join again, and it's
+  // closed if nobody has by the timeout. Round timers start over from the
+  // phase the room was in, while a timed game keeps the time it had left.
+  pub async fn restore_room(
+    &self,
+    snapshot: RoomSnapshot,
+  ) -> Result<(), RestoreRoomError> {
+    let mut state = RoomState::try_from(snapshot)?;
+    let meta = state.get_meta_mut().ok_or(RestoreRoomError::NoRoom)?;
+    meta
+      .everyone_mut()
+      .for_each(|info| info.player_connected = false);
+    meta.update_host();
+    let room_id = meta.room_id;
+    // Served positions aren't kept in snapshots, so boards the room had before
+    // can come up again
+    let served_positions = ServedPositions::default();
+    let generator = self
+      .build_generator(meta.generator_spec.clone(), served_positions.clone())
+      .await?;
+    // A playlist picks up after the last board it gave. Other generators have
+    // no place to keep, so they just start drawing again.
+    if let GeneratorSpec::Playlist { .. } = meta.generator_spec {
+      for _ in 0..meta.boards_drawn {
+        generator.try_generate_solved_position().ok();
+      }
+    }
+
+    let mut room =
+      Room::from_state(room_id, state, generator, served_positions);
+    self.apply_countdown(&mut room);
+    self.apply_game_timer(&mut room);
+    let app_state = self.clone();
+    room.utils.restore_timeout = Some(tokio::spawn(async move {
+      tokio::time::sleep(RESTORED_ROOM_TIMEOUT).await;
+      app_state.close_if_abandoned(room_id).await;
+    }));
+    self.rooms.write().await.insert(room_id, RwLock::new(room));
+    Ok(())
+  }
+
+  async fn close_if_abandoned(&self, room_id: RoomId) {
+    let abandoned = self
+      .with_room_write(room_id, |room| {
+        let abandoned = room.state.get_meta().is_some_and(|meta| {
+          !meta.everyone().any(|info| info.player_connected)
+        });
+        if abandoned {
+          // This is the timeout's own task, which is left to finish
+          room.utils.restore_timeout = None;
+          room.state = RoomState::Closed;
+        }
+        Ok::<_, NoRoomExistsError>(abandoned)
+      })
+      .await
+      .unwrap_or(false);
+    if abandoned {
+      tracing::debug!("Nobody came back to restored room {:?}", room_id);
+      self.clean_up_room(room_id).await;
     }
   }
 
@@ -140,12 +385,26 @@ impl AppState {
       tracing::debug!("Cleaning up room {:?}", room_id);
       // Remove room
       let removed = { self.rooms.write().await.remove(&room_id) };
-      // Cancel countdown if present
+      // Cancel countdown and timers if present
       if let Some(removed) = removed {
-        let countdown = &removed.write().await.utils.countdown;
-        if let Some(countdown) = countdown {
+        let utils = &removed.write().await.utils;
+        if tracing::enabled!(tracing::Level::TRACE) {
+          match serde_json::to_string(&utils.event_log) {
+            Ok(event_log) => {
+              tracing::trace!("Room {:?} event log: {}", room_id, event_log)
+            }
+            Err(err) => tracing::trace!("Unable to write event log: {}", err),
+          }
+        }
+        if let Some(countdown) = &utils.countdown {
           countdown.task.abort();
         }
+        if let Some(game_timer) = &utils.game_timer {
+          game_timer.abort();
+        }
+        if let Some(restore_timeout) = &utils.restore_timeout {
+          restore_timeout.abort();
+        }
       }
     }
   }
@@ -157,7 +416,7 @@ impl AppState {
   pub async fn get_broadcast_channel_pair(
     &self,
     room_id: RoomId,
-  ) -> Result<broadcast::Receiver<ToClientMessage>, NoRoomExistsError> {
+  ) -> Result<broadcast::Receiver<RoomBroadcast>, NoRoomExistsError> {
     self
       .with_room_read(room_id, |room| {
         Ok(room.utils.broadcast_channel.subscribe())
@@ -171,7 +430,7 @@ impl AppState {
   ) -> Result<(), BroadcastError> {
     self
       .with_room_read(room_id, |room| {
-        let msg = ToClientMessage::RoomUpdate(Box::new(room.state.clone()));
+        let msg = RoomBroadcast::RoomUpdate(Arc::new(room.state.clone()));
 
         room
           .utils
@@ -200,9 +459,11 @@ impl AppState {
         let now = Instant::now();
         let time_left = countdown_stop - now;
 
-        let msg = ToClientMessage::CountdownUpdate(CountdownUpdateMessage {
-          server_time_left_millis: time_left.as_millis(),
-        });
+        let msg = RoomBroadcast::Message(ToClientMessage::CountdownUpdate(
+          CountdownUpdateMessage {
+            server_time_left_millis: time_left.as_millis(),
+          },
+        ));
 
         room
           .utils
@@ -222,9 +483,15 @@ impl AppState {
     let app_state = self.clone();
     let room_id = room.utils.room_id;
     let now = Instant::now();
+    let timers = room
+      .state
+      .get_meta()
+      .map(|meta| meta.timers)
+      .unwrap_or_default();
+    let secs = |secs: u32| Duration::from_secs(secs.into());
     match room.state {
       RoomState::RoundStart(_) => {
-        let stop = now + Duration::from_secs(180);
+        let stop = now + secs(timers.round_start_secs);
         room.utils.countdown = Some(Countdown {
           task: tokio::spawn(async move {
             tokio::time::sleep_until(stop).await;
@@ -237,7 +504,7 @@ impl AppState {
         })
       }
       RoomState::RoundBidding(_) => {
-        let stop = now + Duration::from_secs(60);
+        let stop = now + secs(timers.round_bidding_secs);
         room.utils.countdown = Some(Countdown {
           task: tokio::spawn(async move {
             tokio::time::sleep_until(stop).await;
@@ -250,7 +517,7 @@ impl AppState {
         })
       }
       RoomState::RoundSolving(_) => {
-        let stop = now + Duration::from_secs(60);
+        let stop = now + secs(timers.round_solving_secs);
         room.utils.countdown = Some(Countdown {
           task: tokio::spawn(async move {
             tokio::time::sleep_until(stop).await;
@@ -267,6 +534,41 @@ impl AppState {
     }
   }
 
+  // Timed games are timed from the start of their first round. The timer is
+  // set then and whenever the game length changes after that, and dropped
+  // once the game is over.
+  pub fn apply_game_timer(&self, room: &mut Room) {
+    if let Some(game_timer) = room.utils.game_timer.take() {
+      game_timer.abort();
+    }
+    let Some(meta) = room.state.get_meta() else {
+      return;
+    };
+    let GameLength::TimeLimit { minutes } = meta.game_length else {
+      return;
+    };
+    let Some(game_started_at) = meta.game_started_at else {
+      return;
+    };
+    if meta.time_limit_reached || matches!(room.state, RoomState::GameOver(_)) {
+      return;
+    }
+    let elapsed =
+      Duration::from_millis(now_millis().saturating_sub(game_started_at));
+    let time_left =
+      Duration::from_secs(u64::from(minutes) * 60).saturating_sub(elapsed);
+    let app_state = self.clone();
+    let room_id = room.utils.room_id;
+    let stop = Instant::now() + time_left;
+    room.utils.game_timer = Some(tokio::spawn(async move {
+      tokio::time::sleep_until(stop).await;
+      app_state
+        .apply_event(room_id, RoomEvent::TimeLimitReached)
+        .await
+        .ok();
+    }));
+  }
+
   fn _apply_event(
     &self,
     room: &mut Room,
@@ -279,13 +581,16 @@ impl AppState {
       room.utils.room_id
     );
     let original_discriminant = mem::discriminant(&room.state);
+    let original_solver = room.state.get_solver();
+    let original_game_length =
+      room.state.get_meta().map(|meta| meta.game_length);
     let working_state = mem::replace(&mut room.state, RoomState::None);
-    let event_is_yield_solve = matches!(event, RoomEvent::YieldSolve);
 
+    let at_millis = now_millis();
     let EventResult {
       result: next_state,
       error,
-    } = working_state.apply(event);
+    } = working_state.apply_at(event.clone(), at_millis);
     room.state = next_state;
     if let Some(err) = error {
       tracing::debug!(
@@ -294,17 +599,132 @@ impl AppState {
         room.utils.room_id,
         err
       );
-      return Err(err.into());
+      return Err(Box::new(err).into());
     }
+    room.utils.event_log.push(at_millis, event);
 
     let current_discriminant = mem::discriminant(&room.state);
     let state_transition_occurred =
       original_discriminant != current_discriminant;
+    // Yielding or kicking the solver hands the solve to someone new
+    let current_solver = room.state.get_solver();
     let solver_changed =
-      event_is_yield_solve && matches!(room.state, RoomState::RoundSolving(_));
+      current_solver.is_some() && current_solver != original_solver;
     if state_transition_occurred || solver_changed {
       self.apply_countdown(room);
     }
+    let game_started = matches!(
+      &room.state,
+      RoomState::RoundStart(RoundStart { meta, .. }) if meta.round_number == 1
+    );
+    let game_over = matches!(room.state, RoomState::GameOver(_));
+    let game_length_changed =
+      room.state.get_meta().map(|meta| meta.game_length)
+        != original_game_length;
+    if (state_transition_occurred && (game_started || game_over))
+      || game_length_changed
+    {
+      self.apply_game_timer(room);
+    }
+    Ok(())
+  }
+
+  // Boards are drawn here rather than in the state machine, so that the
+  // StartRound event holds everything needed to replay it. A board is only
+  // drawn when the round can actually start, and the event is left without
+  // one otherwise, for the state machine to reject or to end the game when
+  // the generator has run out.
+  pub async fn start_round(
+    &self,
+    room_id: RoomId,
+    player_id: PlayerId,
+  ) -> Result<(), ApplyEventError> {
+    let generator_lock = self.generator_lock(room_id).await?;
+    let _generator_guard = generator_lock.lock().await;
+    let generator = self
+      .with_room_read(room_id, |room| {
+        let can_start = is_host_between_rounds(&room.state, player_id)
+          && !room.utils.generator.is_exhausted();
+        Ok::<_, NoRoomExistsError>(
+          can_start.then(|| room.utils.generator.clone()),
+        )
+      })
+      .await?;
+    // Generators that solve their boards can take a while, so the board is
+    // drawn without holding the room
+    let solved_position = match generator {
+      Some(generator) => Some(Box::new(
+        tokio::task::spawn_blocking(move || {
+          generator.try_generate_solved_position()
+        })
+        .await
+        .expect("Board draw task failed")?,
+      )),
+      None => None,
+    };
+    self
+      .with_room_write(room_id, |room| {
+        self._apply_event(
+          room,
+          RoomEvent::StartRound(StartRound {
+            player_id,
+            solved_position,
+          }),
+        )
+      })
+      .await?;
+    self.broadcast_room(room_id).await.ok();
+    self.broadcast_countdown(room_id).await.ok();
+    Ok(())
+  }
+
+  // A new generator spec is built before the room is touched, so a bad spec
+  // leaves the room as it was. Building can mean solving every listed
+  // position, so it's only done for changes the state machine would accept.
+  async fn update_settings(
+    &self,
+    room_id: RoomId,
+    event: UpdateSettings,
+  ) -> Result<(), ApplyEventError> {
+    let generator_lock = self.generator_lock(room_id).await?;
+    let _generator_guard = generator_lock.lock().await;
+    let (can_update, current_spec, served_positions) = self
+      .with_room_read(room_id, |room| {
+        Ok::<_, NoRoomExistsError>((
+          is_host_between_rounds(&room.state, event.host_id),
+          room
+            .state
+            .get_meta()
+            .map(|meta| meta.generator_spec.clone()),
+          room.utils.served_positions.clone(),
+        ))
+      })
+      .await?;
+    // The room keeps its generator, and whatever it has buffered, unless the
+    // spec actually changes
+    let generator = match &event.generator_spec {
+      Some(generator_spec)
+        if can_update && current_spec.as_ref() != Some(generator_spec) =>
+      {
+        Some(
+          self
+            .build_generator(generator_spec.clone(), served_positions)
+            .await?,
+        )
+      }
+      _ => None,
+    };
+    self
+      .with_room_write(room_id, |room| {
+        self._apply_event(room, RoomEvent::UpdateSettings(event))?;
+        if let Some(generator) = generator {
+          room.utils.generator = generator;
+        }
+        Ok::<_, ApplyEventError>(())
+      })
+      .await?;
+    self.broadcast_room(room_id).await.ok();
+    self.broadcast_countdown(room_id).await.ok();
     Ok(())
   }
 
@@ -335,6 +755,9 @@ impl AppState {
     room_id: RoomId,
     event: RoomEvent,
   ) -> Result<(), ApplyEventError> {
+    if let RoomEvent::UpdateSettings(event) = event {
+      return self.update_settings(room_id, event).await;
+    }
     self
       .with_room_write(room_id, |room| self._apply_event(room, event))
       .await?;
@@ -343,3 +766,460 @@ impl AppState {
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use inertia_core::board_generators::OneMoveSolutionBoardGenerator;
+  use inertia_core::board_generators::PlaylistEntry;
+  use inertia_core::board_generators::PlaylistGenerator;
+  use inertia_core::mechanics::B64EncodedCompressedPosition;
+  use inertia_core::mechanics::CompressedPosition;
+  use inertia_core::mechanics::SolvedPosition;
+  use inertia_core::solvers::B64EncodedCompressedSolution;
+  use inertia_core::solvers::CompressedSolution;
+  use inertia_core::state::data::PlayerId;
+  use inertia_core::state::data::PlayerName;
+  use inertia_core::state::data::PlayerReconnectKey;
+  use inertia_core::state::data::PlayerRole;
+  use inertia_core::state::event::connect::Connect;
+  use inertia_core::state::event::make_bid::MakeBid;
+  use inertia_core::test_support::UnavailableGenerator;
+  use sqlx::sqlite::SqlitePoolOptions;
+
+  const ROOM_ID: RoomId = RoomId(0);
+  const PLAYER_ID: PlayerId = PlayerId(1);
+
+  async fn create_room(
+    timers: RoundTimers,
+    game_length: GameLength,
+  ) -> AppState {
+    let state = AppState {
+      db_pool: SqlitePoolOptions::new()
+        .connect_lazy("sqlite::memory:")
+        .unwrap(),
+      db_bucket_sizes: DbBucketSizes::default(),
+      rooms: Arc::new(RwLock::new(HashMap::new())),
+    };
+    state
+      .ensure_room_exists(
+        ROOM_ID,
+        GeneratorSpec::OneMove,
+        timers,
+        ScoringPolicy::default(),
+        game_length,
+      )
+      .await
+      .unwrap();
+    state
+      .apply_event(
+        ROOM_ID,
+        RoomEvent::Connect(Connect {
+          player_name: PlayerName::from("test"),
+          player_id: PLAYER_ID,
+          player_reconnect_key: PlayerReconnectKey(123),
+          role: PlayerRole::Player,
+        }),
+      )
+      .await
+      .unwrap();
+    state
+  }
+
+  async fn start_round(state: &AppState) {
+    state.start_round(ROOM_ID, PLAYER_ID).await.unwrap();
+  }
+
+  async fn room_state_after(state: &AppState, secs: u64) -> String {
+    tokio::time::sleep(Duration::from_secs(secs)).await;
+    state
+      .with_room_read(ROOM_ID, |room| {
+        Ok::<_, NoRoomExistsError>(room.state.to_string())
+      })
+      .await
+      .unwrap()
+  }
+
+  #[tokio::test(start_paused = true)]
+  async fn countdowns_use_room_timers() {
+    let state = create_room(
+      RoundTimers {
+        round_start_secs: 30,
+        round_bidding_secs: 20,
+        round_solving_secs: 15,
+      },
+      GameLength::default(),
+    )
+    .await;
+    start_round(&state).await;
+
+    assert_eq!(room_state_after(&state, 29).await, "RoundStart");
+    state
+      .apply_event(
+        ROOM_ID,
+        RoomEvent::MakeBid(MakeBid {
+          player_id: PLAYER_ID,
+          bid_value: 5,
+        }),
+      )
+      .await
+      .unwrap();
+    assert_eq!(room_state_after(&state, 19).await, "RoundBidding");
+    assert_eq!(room_state_after(&state, 2).await, "RoundSolving");
+    assert_eq!(room_state_after(&state, 13).await, "RoundSolving");
+    assert_eq!(room_state_after(&state, 2).await, "RoundSummary");
+  }
+
+  #[tokio::test(start_paused = true)]
+  async fn host_changes_timers_between_rounds() {
+    let state =
+      create_room(RoundTimers::default(), GameLength::default()).await;
+    state
+      .apply_event(
+        ROOM_ID,
+        RoomEvent::UpdateSettings(UpdateSettings {
+          host_id: PLAYER_ID,
+          generator_spec: None,
+          timers: Some(RoundTimers {
+            round_start_secs: 12,
+            ..RoundTimers::default()
+          }),
+          scoring: None,
+          game_length: None,
+          solution_visibility: None,
+        }),
+      )
+      .await
+      .unwrap();
+    start_round(&state).await;
+
+    assert_eq!(room_state_after(&state, 11).await, "RoundStart");
+    assert_eq!(room_state_after(&state, 2).await, "RoundSummary");
+  }
+
+  #[tokio::test]
+  async fn rejects_out_of_bounds_timers() {
+    let state =
+      create_room(RoundTimers::default(), GameLength::default()).await;
+    let result = state
+      .ensure_room_exists(
+        RoomId(1),
+        GeneratorSpec::OneMove,
+        RoundTimers {
+          round_start_secs: RoundTimers::MAX_SECS + 1,
+          ..RoundTimers::default()
+        },
+        ScoringPolicy::default(),
+        GameLength::default(),
+      )
+      .await;
+    assert!(matches!(result, Err(CreateRoomError::Timers(_))));
+    assert_eq!(state.get_room_count().await, 1);
+  }
+
+  #[tokio::test(start_paused = true)]
+  async fn time_limit_ends_game_after_round() {
+    let state =
+      create_room(RoundTimers::default(), GameLength::TimeLimit { minutes: 1 })
+        .await;
+    start_round(&state).await;
+
+    // Time runs out during the first round, which is still played out
+    assert_eq!(room_state_after(&state, 61).await, "RoundStart");
+    assert_eq!(room_state_after(&state, 120).await, "GameOver");
+  }
+
+  async fn set_generator(
+    state: &AppState,
+    generator: Box<dyn SolvedPositionGenerator>,
+  ) {
+    state
+      .with_room_write(ROOM_ID, |room| {
+        room.utils.generator = generator;
+        Ok::<_, NoRoomExistsError>(())
+      })
+      .await
+      .unwrap();
+  }
+
+  #[tokio::test]
+  async fn start_round_board_unavailable() {
+    let state =
+      create_room(RoundTimers::default(), GameLength::default()).await;
+    set_generator(&state, Box::new(UnavailableGenerator)).await;
+
+    let result = state.start_round(ROOM_ID, PLAYER_ID).await;
+    assert!(matches!(result, Err(ApplyEventError::BoardUnavailable(_))));
+    assert_eq!(room_state_after(&state, 0).await, "RoundSummary");
+  }
+
+  #[tokio::test]
+  async fn exhausted_generator_ends_game() {
+    let state =
+      create_room(RoundTimers::default(), GameLength::default()).await;
+    set_generator(&state, Box::new(PlaylistGenerator::new(Vec::new()))).await;
+
+    start_round(&state).await;
+    assert_eq!(room_state_after(&state, 0).await, "GameOver");
+  }
+
+  #[tokio::test(start_paused = true)]
+  async fn event_log_replays_room() {
+    let state =
+      create_room(RoundTimers::default(), GameLength::default()).await;
+    start_round(&state).await;
+    state
+      .apply_event(
+        ROOM_ID,
+        RoomEvent::MakeBid(MakeBid {
+          player_id: PLAYER_ID,
+          bid_value: 3,
+        }),
+      )
+      .await
+      .unwrap();
+    // Not the host, so this is rejected and left out of the log
+    assert!(state.start_round(ROOM_ID, PlayerId(2)).await.is_err());
+
+    let (room_state, event_log) = state
+      .with_room_read(ROOM_ID, |room| {
+        Ok::<_, NoRoomExistsError>((
+          room.state.clone(),
+          serde_json::to_string(&room.utils.event_log).unwrap(),
+        ))
+      })
+      .await
+      .unwrap();
+    let event_log: EventLog = serde_json::from_str(&event_log).unwrap();
+    assert_eq!(event_log.events().len(), 3);
+    assert_eq!(event_log.replay().unwrap(), room_state);
+  }
+
+  #[tokio::test]
+  async fn restored_playlists_keep_their_place() {
+    let state =
+      create_room(RoundTimers::default(), GameLength::default()).await;
+    let source = OneMoveSolutionBoardGenerator::new();
+    let solved_positions: Vec<SolvedPosition> = (0..2)
+      .map(|seed| source.generate_solved_position_from_seed(seed))
+      .collect();
+    let entries = solved_positions
+      .iter()
+      .map(|solved_position| PlaylistEntry {
+        position: CompressedPosition::from(solved_position.position).into(),
+        solution: Some(
+          CompressedSolution::from(&solved_position.solution).into(),
+        ),
+      })
+      .collect();
+    state
+      .apply_event(
+        ROOM_ID,
+        RoomEvent::UpdateSettings(UpdateSettings {
+          host_id: PLAYER_ID,
+          generator_spec: Some(GeneratorSpec::Playlist { entries }),
+          timers: None,
+          scoring: None,
+          game_length: None,
+          solution_visibility: None,
+        }),
+      )
+      .await
+      .unwrap();
+    start_round(&state).await;
+
+    let restored = AppState {
+      rooms: Arc::new(RwLock::new(HashMap::new())),
+      ..state.clone()
+    };
+    for snapshot in state.snapshot_rooms().await {
+      restored.restore_room(snapshot).await.unwrap();
+    }
+    let generator = restored
+      .with_room_read(ROOM_ID, |room| {
+        Ok::<_, NoRoomExistsError>(room.utils.generator.clone())
+      })
+      .await
+      .unwrap();
+    assert_eq!(
+      generator.try_generate_solved_position().unwrap().position,
+      solved_positions[1].position
+    );
+  }
+
+  #[tokio::test(start_paused = true)]
+  async fn restores_room_from_snapshot() {
+    let state =
+      create_room(RoundTimers::default(), GameLength::default()).await;
+    start_round(&state).await;
+    let snapshots =
+      serde_json::to_string(&state.snapshot_rooms().await).unwrap();
+    let snapshots: Vec<RoomSnapshot> =
+      serde_json::from_str(&snapshots).unwrap();
+
+    let restored = AppState {
+      rooms: Arc::new(RwLock::new(HashMap::new())),
+      ..state.clone()
+    };
+    for snapshot in snapshots {
+      restored.restore_room(snapshot).await.unwrap();
+    }
+    let meta = restored
+      .with_room_read(ROOM_ID, |room| {
+        Ok::<_, NoRoomExistsError>(room.state.get_meta().unwrap().clone())
+      })
+      .await
+      .unwrap();
+    assert_eq!(meta.round_number, 1);
+    assert!(!meta.player_info[&PLAYER_ID].player_connected);
+    assert_eq!(meta.host, None);
+
+    // The round picks up where it was, with its countdown started over
+    assert_eq!(room_state_after(&restored, 0).await, "RoundStart");
+    assert_eq!(
+      room_state_after(
+        &restored,
+        u64::from(RoundTimers::default().round_start_secs) + 1
+      )
+      .await,
+      "RoundSummary"
+    );
+  }
+
+  #[tokio::test]
+  async fn views_leave_out_playlist() {
+    let state =
+      create_room(RoundTimers::default(), GameLength::default()).await;
+    let solved_position =
+      OneMoveSolutionBoardGenerator::new().generate_solved_position();
+    let position = B64EncodedCompressedPosition::from(
+      CompressedPosition::from(solved_position.position),
+    );
+    let solution = B64EncodedCompressedSolution::from(
+      CompressedSolution::from(&solved_position.solution),
+    );
+    state
+      .apply_event(
+        ROOM_ID,
+        RoomEvent::UpdateSettings(UpdateSettings {
+          host_id: PLAYER_ID,
+          generator_spec: Some(GeneratorSpec::Playlist {
+            entries: vec![PlaylistEntry {
+              position: position.clone(),
+              solution: Some(solution.clone()),
+            }],
+          }),
+          timers: None,
+          scoring: None,
+          game_length: None,
+          solution_visibility: None,
+        }),
+      )
+      .await
+      .unwrap();
+
+    let view = state
+      .with_room_read(ROOM_ID, |room| {
+        Ok::<_, NoRoomExistsError>(
+          serde_json::to_string(&room.state.view(PLAYER_ID)).unwrap(),
+        )
+      })
+      .await
+      .unwrap();
+    assert!(!view.contains(&format!("\"{}\"", position.0)));
+    assert!(!view.contains(&format!("\"{}\"", solution.0)));
+    let view: serde_json::Value = serde_json::from_str(&view).unwrap();
+    assert_eq!(
+      view["state"]["content"]["meta"]["generator"],
+      serde_json::json!({ "type": "Playlist", "content": { "length": 1 } })
+    );
+  }
+
+  #[tokio::test]
+  async fn settings_from_non_host_skip_generator() {
+    let state =
+      create_room(RoundTimers::default(), GameLength::default()).await;
+    // The spec is never built, so the event fails for not coming from the
+    // host rather than for the bad position
+    let result = state
+      .apply_event(
+        ROOM_ID,
+        RoomEvent::UpdateSettings(UpdateSettings {
+          host_id: PlayerId(2),
+          generator_spec: Some(GeneratorSpec::PositionList {
+            positions: vec![B64EncodedCompressedPosition("nope".to_owned())],
+          }),
+          timers: None,
+          scoring: None,
+          game_length: None,
+          solution_visibility: None,
+        }),
+      )
+      .await;
+    assert!(matches!(
+      result,
+      Err(ApplyEventError::ApplyRoomEventError(_))
+    ));
+  }
+
+  #[tokio::test(start_paused = true)]
+  async fn time_limit_set_mid_game() {
+    let state =
+      create_room(RoundTimers::default(), GameLength::default()).await;
+    start_round(&state).await;
+    assert_eq!(
+      room_state_after(
+        &state,
+        u64::from(RoundTimers::default().round_start_secs) + 1
+      )
+      .await,
+      "RoundSummary"
+    );
+    state
+      .apply_event(
+        ROOM_ID,
+        RoomEvent::UpdateSettings(UpdateSettings {
+          host_id: PLAYER_ID,
+          generator_spec: None,
+          timers: None,
+          scoring: None,
+          game_length: Some(GameLength::TimeLimit { minutes: 1 }),
+          solution_visibility: None,
+        }),
+      )
+      .await
+      .unwrap();
+    start_round(&state).await;
+
+    assert_eq!(room_state_after(&state, 61).await, "RoundStart");
+    assert_eq!(room_state_after(&state, 120).await, "GameOver");
+  }
+
+  #[tokio::test(start_paused = true)]
+  async fn restored_rooms_close_when_abandoned() {
+    let state = create_room(
+      RoundTimers::default(),
+      GameLength::TimeLimit { minutes: 10 },
+    )
+    .await;
+    start_round(&state).await;
+    let snapshots = state.snapshot_rooms().await;
+    let restored = AppState {
+      rooms: Arc::new(RwLock::new(HashMap::new())),
+      ..state.clone()
+    };
+    for snapshot in snapshots {
+      restored.restore_room(snapshot).await.unwrap();
+    }
+    let has_game_timer = restored
+      .with_room_read(ROOM_ID, |room| {
+        Ok::<_, NoRoomExistsError>(room.utils.game_timer.is_some())
+      })
+      .await
+      .unwrap();
+    assert!(has_game_timer);
+
+    tokio::time::sleep(RESTORED_ROOM_TIMEOUT + Duration::from_secs(1)).await;
+    assert_eq!(restored.get_room_count().await, 0);
+  }
+}
@@ -3,9 +3,16 @@ use inertia_core::message::ToClientMessage;
 use inertia_core::state::data::PlayerId;
 use inertia_core::state::data::RoomId;
 use inertia_core::state::event::apply_event::RoomEvent;
+use inertia_core::state::event::end_game::EndGame;
+use inertia_core::state::event::kick_player::KickPlayer;
 use inertia_core::state::event::make_bid::MakeBid;
+use inertia_core::state::event::new_game::NewGame;
 use inertia_core::state::event::ready_bid::ReadyBid;
 use inertia_core::state::event::ready_bid::UnreadyBid;
+use inertia_core::state::event::rename::Rename;
+use inertia_core::state::event::set_role::SetRole;
+use inertia_core::state::event::transfer_host::TransferHost;
+use inertia_core::state::event::update_settings::UpdateSettings;
 use inertia_core::state::event::update_solution::UpdateSolution;
 use thiserror::Error;
 use tokio::sync::mpsc;
@@ -43,11 +50,83 @@ pub async fn handle_message_from_client(
     FromClientMessage::Join(_) => {
       Err(HandleMessageError::UnexpectedMessageType(msg))?;
     }
-    FromClientMessage::Rename(_) => {
-      Err(HandleMessageError::UnexpectedMessageType(msg))?;
+    FromClientMessage::Rename(rename_message) => {
+      state
+        .apply_event(
+          room_id,
+          RoomEvent::Rename(Rename {
+            player_id,
+            player_name: rename_message.player_name,
+          }),
+        )
+        .await?
+    }
+    FromClientMessage::SetRole(set_role_message) => {
+      state
+        .apply_event(
+          room_id,
+          RoomEvent::SetRole(SetRole {
+            player_id,
+            role: set_role_message.role,
+          }),
+        )
+        .await?
     }
     FromClientMessage::StartRound => {
-      state.apply_event(room_id, RoomEvent::StartRound).await?;
+      state.start_round(room_id, player_id).await?
+    }
+    FromClientMessage::KickPlayer(kick_player_message) => {
+      state
+        .apply_event(
+          room_id,
+          RoomEvent::KickPlayer(KickPlayer {
+            host_id: player_id,
+            player_id: kick_player_message.player_id,
+          }),
+        )
+        .await?
+    }
+    FromClientMessage::TransferHost(transfer_host_message) => {
+      state
+        .apply_event(
+          room_id,
+          RoomEvent::TransferHost(TransferHost {
+            host_id: player_id,
+            player_id: transfer_host_message.player_id,
+          }),
+        )
+        .await?
+    }
+    FromClientMessage::UpdateSettings(update_settings_message) => {
+      state
+        .apply_event(
+          room_id,
+          RoomEvent::UpdateSettings(UpdateSettings {
+            host_id: player_id,
+            generator_spec: update_settings_message.generator,
+            timers: update_settings_message.timers,
+            scoring: update_settings_message.scoring,
+            game_length: update_settings_message.game_length,
+            solution_visibility: update_settings_message.solution_visibility,
+          }),
+        )
+        .await?
+    }
+    FromClientMessage::EndGame => {
+      state
+        .apply_event(
+          room_id,
+          RoomEvent::EndGame(EndGame { host_id: player_id }),
+        )
+        .await?
+    }
+    FromClientMessage::NewGame => {
+      state
+        .apply_event(
+          room_id,
+          RoomEvent::NewGame(NewGame { host_id: player_id }),
+        )
+        .await?
     }
     FromClientMessage::Bid(bid_message) => {
       state
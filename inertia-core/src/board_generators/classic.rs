@@ -1,10 +1,12 @@
-use std::collections::HashSet;
+use std::collections::BTreeSet;
 use std::ops::Range;
 
 use rand::seq::IteratorRandom;
 use rand::seq::SliceRandom;
-use rand::thread_rng;
 use rand::Rng;
+use rand::RngCore;
+use serde::Deserialize;
+use serde::Serialize;
 
 use crate::mechanics::ActorSquares;
 use crate::mechanics::Position;
@@ -12,12 +14,84 @@ use crate::mechanics::PositionGenerator;
 use crate::mechanics::Square;
 use crate::mechanics::WalledBoard;
 
-#[derive(Debug, Clone, Copy)]
-pub struct ClassicFreeGoalBoardGenerator;
+#[cfg(feature = "web")]
+use {tsify::Tsify, wasm_bindgen::prelude::wasm_bindgen};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "web", derive(Tsify), tsify(into_wasm_abi, from_wasm_abi))]
+#[serde(tag = "type", content = "content")]
+pub enum GoalPlacement {
+  // Any square outside the central box that no actor starts on
+  Anywhere,
+  // A square with exactly one horizontal and one vertical wall. With the given
+  // probability, the board edges also count as walls for this purpose.
+  Corner { edge_chance: f64 },
+}
+
+// An inclusive rectangle of squares
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "web", derive(Tsify), tsify(into_wasm_abi, from_wasm_abi))]
+pub struct SquareRegion {
+  pub min_row: usize,
+  pub max_row: usize,
+  pub min_col: usize,
+  pub max_col: usize,
+}
+
+impl SquareRegion {
+  pub fn contains(&self, square: Square) -> bool {
+    let (row, col) = square.as_row_col();
+    (self.min_row..=self.max_row).contains(&row)
+      && (self.min_col..=self.max_col).contains(&col)
+  }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "web", derive(Tsify), tsify(into_wasm_abi, from_wasm_abi))]
+#[serde(default)]
+pub struct ClassicGeneratorConfig {
+  pub min_corners_per_quadrant: usize,
+  pub max_corners_per_quadrant: usize,
+  // Walls are alternated between the two halves of each side
+  pub edge_walls_per_side: usize,
+  pub central_box: bool,
+  pub goal_placement: GoalPlacement,
+  pub actor_exclusion_zones: Vec<SquareRegion>,
+}
+
+impl Default for ClassicGeneratorConfig {
+  fn default() -> Self {
+    Self {
+      min_corners_per_quadrant: 3,
+      max_corners_per_quadrant: 5,
+      edge_walls_per_side: 2,
+      central_box: true,
+      goal_placement: GoalPlacement::Corner { edge_chance: 0.25 },
+      actor_exclusion_zones: Vec::new(),
+    }
+  }
+}
+
+pub(super) const CENTRAL_BOX: SquareRegion = SquareRegion {
+  min_row: 7,
+  max_row: 8,
+  min_col: 7,
+  max_col: 8,
+};
+
+#[derive(Debug, Clone)]
+pub struct ClassicFreeGoalBoardGenerator {
+  inner: ClassicBoardGenerator,
+}
 
 impl ClassicFreeGoalBoardGenerator {
   pub fn new() -> Self {
-    Self
+    Self {
+      inner: ClassicBoardGenerator::with_config(ClassicGeneratorConfig {
+        goal_placement: GoalPlacement::Anywhere,
+        ..Default::default()
+      }),
+    }
   }
 }
 
@@ -28,45 +102,27 @@ impl Default for ClassicFreeGoalBoardGenerator {
 }
 
 impl PositionGenerator for ClassicFreeGoalBoardGenerator {
-  fn generate_position(&self) -> Position {
-    let mut walled_board = WalledBoard::EMPTY;
-    let mut rng = thread_rng();
-
-    add_central_box(&mut walled_board);
-    add_edge_walls(&mut rng, &mut walled_board);
-    add_midboard_corners(&mut rng, &mut walled_board);
-
-    // Exclude central box for goal and actors. Squares: 119, 120, 135, 136
-    let mut goal_and_actor_squares: [u8; 5] = [0, 0, 0, 0, 0];
-    let mut goal_and_actor_candidates: Vec<u8> = Vec::new();
-    goal_and_actor_candidates.extend(0..=118);
-    goal_and_actor_candidates.extend(121..=134);
-    goal_and_actor_candidates.extend(137..=255);
-    goal_and_actor_candidates
-      .iter()
-      .cloned()
-      .choose_multiple_fill(&mut rng, &mut goal_and_actor_squares);
-    goal_and_actor_squares.shuffle(&mut rng);
-
-    Position {
-      goal: Square(goal_and_actor_squares[0]),
-      actor_squares: ActorSquares([
-        Square(goal_and_actor_squares[1]),
-        Square(goal_and_actor_squares[2]),
-        Square(goal_and_actor_squares[3]),
-        Square(goal_and_actor_squares[4]),
-      ]),
-      walled_board,
-    }
+  fn generate_position_with_rng(&self, rng: &mut dyn RngCore) -> Position {
+    self.inner.generate_position_with_rng(rng)
   }
 }
 
-#[derive(Debug, Clone, Copy)]
-pub struct ClassicBoardGenerator;
+#[derive(Debug, Clone)]
+pub struct ClassicBoardGenerator {
+  config: ClassicGeneratorConfig,
+}
 
 impl ClassicBoardGenerator {
   pub fn new() -> Self {
-    Self
+    Self::with_config(ClassicGeneratorConfig::default())
+  }
+
+  pub fn with_config(config: ClassicGeneratorConfig) -> Self {
+    Self { config }
+  }
+
+  pub fn config(&self) -> &ClassicGeneratorConfig {
+    &self.config
   }
 }
 
@@ -77,49 +133,75 @@ impl Default for ClassicBoardGenerator {
 }
 
 impl PositionGenerator for ClassicBoardGenerator {
-  fn generate_position(&self) -> Position {
+  fn generate_position_with_rng(&self, rng: &mut dyn RngCore) -> Position {
+    let config = &self.config;
     let mut walled_board = WalledBoard::EMPTY;
-    let mut rng = thread_rng();
-
-    add_central_box(&mut walled_board);
-    add_edge_walls(&mut rng, &mut walled_board);
-    add_midboard_corners(&mut rng, &mut walled_board);
-
-    // Exclude central box for goal and actors. Squares: 119, 120, 135, 136
-    let forbidden_squares: [u8; 4] = [119, 120, 135, 136];
-    let mut actor_squares: [u8; 4] = [0, 0, 0, 0];
-    let mut actor_square_candidates: Vec<u8> = (0..=255)
-      .filter(|square| !forbidden_squares.contains(square))
-      .collect();
-    actor_square_candidates
-      .iter()
-      .cloned()
-      .choose_multiple_fill(&mut rng, &mut actor_squares);
-    actor_square_candidates.shuffle(&mut rng);
-
-    let allow_edges = rng.gen_bool(0.25);
-
-    let goal_square = (0..=255)
-      .filter(|square| {
-        !forbidden_squares.contains(square) && !actor_squares.contains(square)
-      })
-      .filter(|square| {
-        walled_board
-          .walls_for_square(*square, allow_edges)
-          .is_corner()
+
+    if config.central_box {
+      add_central_box(&mut walled_board);
+    }
+    add_edge_walls(rng, &mut walled_board, config.edge_walls_per_side);
+    add_midboard_corners(
+      rng,
+      &mut walled_board,
+      config.min_corners_per_quadrant,
+      config.max_corners_per_quadrant,
+    );
+
+    // Nothing is placed inside the central box
+    let is_in_central_box =
+      |square: Square| config.central_box && CENTRAL_BOX.contains(square);
+
+    let mut actor_squares: [Square; 4] = [Square(0); 4];
+    let num_actor_squares = (0..=255)
+      .map(Square)
+      .filter(|&square| {
+        !is_in_central_box(square)
+          && !config
+            .actor_exclusion_zones
+            .iter()
+            .any(|zone| zone.contains(square))
       })
-      .choose(&mut rng)
-      .expect("There will be always square that satisfies these conditions");
+      .choose_multiple_fill(rng, &mut actor_squares);
+    if num_actor_squares < actor_squares.len() {
+      // Exclusion zones cover too much of the board, so fall back to
+      // anywhere outside the central box
+      (0..=255)
+        .map(Square)
+        .filter(|&square| !is_in_central_box(square))
+        .choose_multiple_fill(rng, &mut actor_squares);
+    }
+    actor_squares.shuffle(rng);
+
+    let goal_candidates = (0..=255).map(Square).filter(|&square| {
+      !is_in_central_box(square) && !actor_squares.contains(&square)
+    });
+    let goal_square = match config.goal_placement {
+      GoalPlacement::Anywhere => goal_candidates.choose(rng),
+      GoalPlacement::Corner { edge_chance } => {
+        let allow_edges = rng.gen_bool(edge_chance.clamp(0.0, 1.0));
+        goal_candidates
+          .clone()
+          .filter(|&square| {
+            walled_board
+              .walls_for_square(square, allow_edges)
+              .is_corner()
+          })
+          .choose(rng)
+          .or_else(|| goal_candidates.choose(rng))
+      }
+    }
+    .expect("There will always be a square not occupied by an actor");
 
     Position {
-      goal: Square(goal_square),
-      actor_squares: ActorSquares(actor_squares.map(Square)),
+      goal: goal_square,
+      actor_squares: ActorSquares(actor_squares),
       walled_board,
     }
   }
 }
 
-fn add_central_box(walled_board: &mut WalledBoard) {
+pub(super) fn add_central_box(walled_board: &mut WalledBoard) {
   walled_board.set_wall_up((7, 7), true);
   walled_board.set_wall_left((7, 7), true);
 
@@ -133,61 +215,69 @@ fn add_central_box(walled_board: &mut WalledBoard) {
   walled_board.set_wall_right((8, 8), true);
 }
 
-fn add_edge_walls<R: Rng>(rng: &mut R, walled_board: &mut WalledBoard) {
-  walled_board.set_wall_down(((1..7).choose(rng).unwrap(), 0), true);
-  walled_board.set_wall_down(((8..14).choose(rng).unwrap(), 0), true);
-
-  walled_board.set_wall_down(((1..7).choose(rng).unwrap(), 15), true);
-  walled_board.set_wall_down(((8..14).choose(rng).unwrap(), 15), true);
-
-  walled_board.set_wall_left((0, (1..7).choose(rng).unwrap()), true);
-  walled_board.set_wall_left((0, (8..14).choose(rng).unwrap()), true);
-
-  walled_board.set_wall_left((15, (1..7).choose(rng).unwrap()), true);
-  walled_board.set_wall_left((15, (8..14).choose(rng).unwrap()), true);
-}
-
-fn add_midboard_corners<R: Rng>(rng: &mut R, walled_board: &mut WalledBoard) {
-  let mut quad_handlers: [fn(&mut R, &mut WalledBoard); 4] = [
-    add_quad1_corners,
-    add_quad2_corners,
-    add_quad3_corners,
-    add_quad4_corners,
-  ];
-  quad_handlers.shuffle(rng);
-  quad_handlers
-    .iter()
-    .for_each(|quad_handler| quad_handler(rng, walled_board));
-}
-
-fn add_quad1_corners<R: Rng>(rng: &mut R, walled_board: &mut WalledBoard) {
-  let num_corners = (3..=5).choose(rng).unwrap();
-  add_corners_in_range(rng, walled_board, num_corners, 1..8, 1..8);
-}
-
-fn add_quad2_corners<R: Rng>(rng: &mut R, walled_board: &mut WalledBoard) {
-  let num_corners = (3..=5).choose(rng).unwrap();
-  add_corners_in_range(rng, walled_board, num_corners, 1..8, 8..15);
+fn add_edge_walls<R: Rng + ?Sized>(
+  rng: &mut R,
+  walled_board: &mut WalledBoard,
+  walls_per_side: usize,
+) {
+  // Vertical walls along the top and bottom rows, then horizontal walls along
+  // the left and right columns
+  for edge in [0, 15] {
+    for offset in pick_edge_wall_offsets(rng, walls_per_side) {
+      walled_board.set_wall_left((edge, offset), true);
+    }
+  }
+  for edge in [0, 15] {
+    for offset in pick_edge_wall_offsets(rng, walls_per_side) {
+      walled_board.set_wall_down((offset, edge), true);
+    }
+  }
 }
 
-fn add_quad3_corners<R: Rng>(rng: &mut R, walled_board: &mut WalledBoard) {
-  let num_corners = (3..=5).choose(rng).unwrap();
-  add_corners_in_range(rng, walled_board, num_corners, 8..15, 1..8);
+// Distinct offsets along one side, alternating between the two halves so that
+// they stay spread out. Offsets never sit next to a corner or the midpoint.
+fn pick_edge_wall_offsets<R: Rng + ?Sized>(
+  rng: &mut R,
+  walls_per_side: usize,
+) -> Vec<usize> {
+  let mut halves = [(1..7).collect::<Vec<usize>>(), (8..14).collect()];
+  let mut offsets = Vec::with_capacity(walls_per_side);
+  for wall in 0..walls_per_side {
+    let half = &mut halves[wall % 2];
+    if half.is_empty() {
+      break;
+    }
+    offsets.push(half.swap_remove(rng.gen_range(0..half.len())));
+  }
+  offsets
 }
 
-fn add_quad4_corners<R: Rng>(rng: &mut R, walled_board: &mut WalledBoard) {
-  let num_corners = (3..=5).choose(rng).unwrap();
-  add_corners_in_range(rng, walled_board, num_corners, 8..15, 8..15);
+fn add_midboard_corners<R: Rng + ?Sized>(
+  rng: &mut R,
+  walled_board: &mut WalledBoard,
+  min_corners_per_quadrant: usize,
+  max_corners_per_quadrant: usize,
+) {
+  let mut quadrants =
+    [(1..8, 1..8), (1..8, 8..15), (8..15, 1..8), (8..15, 8..15)];
+  quadrants.shuffle(rng);
+  let max_corners_per_quadrant =
+    max_corners_per_quadrant.max(min_corners_per_quadrant);
+  for (row_range, col_range) in quadrants {
+    let num_corners =
+      rng.gen_range(min_corners_per_quadrant..=max_corners_per_quadrant);
+    add_corners_in_range(rng, walled_board, num_corners, row_range, col_range);
+  }
 }
 
-fn add_corners_in_range<R: Rng>(
+fn add_corners_in_range<R: Rng + ?Sized>(
   rng: &mut R,
   walled_board: &mut WalledBoard,
   num_corners: usize,
   row_range: Range<usize>,
   col_range: Range<usize>,
 ) {
-  let mut candidate_squares: HashSet<(usize, usize)> = HashSet::new();
+  let mut candidate_squares: BTreeSet<(usize, usize)> = BTreeSet::new();
   for row in row_range {
     for col in col_range.clone() {
       candidate_squares.insert((row, col));
@@ -262,3 +352,90 @@ fn add_corners_in_range<R: Rng>(
     }
   }
 }
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn same_seed_same_position() {
+    for seed in 0..20 {
+      assert_eq!(
+        ClassicBoardGenerator::new().generate_position_from_seed(seed),
+        ClassicBoardGenerator::new().generate_position_from_seed(seed)
+      );
+      assert_eq!(
+        ClassicFreeGoalBoardGenerator::new().generate_position_from_seed(seed),
+        ClassicFreeGoalBoardGenerator::new().generate_position_from_seed(seed)
+      );
+    }
+  }
+
+  #[test]
+  fn different_seeds_different_positions() {
+    assert_ne!(
+      ClassicBoardGenerator::new().generate_position_from_seed(1),
+      ClassicBoardGenerator::new().generate_position_from_seed(2)
+    );
+  }
+
+  #[test]
+  fn seeded_positions_are_well_formed() {
+    let forbidden_squares = [119, 120, 135, 136].map(Square);
+    for seed in 0..20 {
+      let Position {
+        walled_board,
+        actor_squares,
+        goal,
+      } = ClassicBoardGenerator::new().generate_position_from_seed(seed);
+      assert!(!forbidden_squares.contains(&goal));
+      assert!(!actor_squares.0.contains(&goal));
+      for (index, square) in actor_squares.0.iter().enumerate() {
+        assert!(!forbidden_squares.contains(square));
+        assert!(!actor_squares.0[index + 1..].contains(square));
+      }
+      assert!(
+        walled_board.walls_for_square(goal, false).is_corner()
+          || walled_board.walls_for_square(goal, true).is_corner()
+      );
+    }
+  }
+
+  #[test]
+  fn config_without_walls() {
+    let generator =
+      ClassicBoardGenerator::with_config(ClassicGeneratorConfig {
+        min_corners_per_quadrant: 0,
+        max_corners_per_quadrant: 0,
+        edge_walls_per_side: 0,
+        central_box: false,
+        goal_placement: GoalPlacement::Anywhere,
+        actor_exclusion_zones: Vec::new(),
+      });
+    for seed in 0..20 {
+      let position = generator.generate_position_from_seed(seed);
+      assert_eq!(position.walled_board, WalledBoard::EMPTY);
+    }
+  }
+
+  #[test]
+  fn config_actor_exclusion_zones() {
+    let top_half = SquareRegion {
+      min_row: 0,
+      max_row: 7,
+      min_col: 0,
+      max_col: 15,
+    };
+    let generator =
+      ClassicBoardGenerator::with_config(ClassicGeneratorConfig {
+        actor_exclusion_zones: vec![top_half],
+        ..Default::default()
+      });
+    for seed in 0..20 {
+      let position = generator.generate_position_from_seed(seed);
+      for square in position.actor_squares.0 {
+        assert!(!top_half.contains(square));
+      }
+    }
+  }
+}
@@ -1,3 +1,5 @@
+use rand::RngCore;
+
 use crate::mechanics::ActorSquares;
 use crate::mechanics::Position;
 use crate::mechanics::PositionGenerator;
@@ -20,7 +22,7 @@ impl Default for EmptyMiddleGoalBoardGenerator {
 }
 
 impl PositionGenerator for EmptyMiddleGoalBoardGenerator {
-  fn generate_position(&self) -> Position {
+  fn generate_position_with_rng(&self, _rng: &mut dyn RngCore) -> Position {
     Position {
       goal: Square::from_row_col(8, 8),
       actor_squares: ActorSquares([Square(0), Square(1), Square(2), Square(3)]),
@@ -6,3 +6,27 @@ pub use empty_middle_goal::*;
 
 mod one_move_solution;
 pub use one_move_solution::*;
+
+mod tiles;
+pub use tiles::*;
+
+mod targeted;
+pub use targeted::*;
+
+mod unique_solution;
+pub use unique_solution::*;
+
+mod solving;
+pub use solving::*;
+
+mod seeded;
+pub use seeded::*;
+
+mod position_list;
+pub use position_list::*;
+
+mod playlist;
+pub use playlist::*;
+
+mod registry;
+pub use registry::*;
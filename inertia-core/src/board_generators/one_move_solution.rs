@@ -1,3 +1,5 @@
+use rand::RngCore;
+
 use crate::mechanics::ActorSquares;
 use crate::mechanics::Direction;
 use crate::mechanics::Position;
@@ -24,7 +26,10 @@ impl Default for OneMoveSolutionBoardGenerator {
 }
 
 impl SolvedPositionGenerator for OneMoveSolutionBoardGenerator {
-  fn generate_solved_position(&self) -> SolvedPosition {
+  fn generate_solved_position_with_rng(
+    &self,
+    _rng: &mut dyn RngCore,
+  ) -> SolvedPosition {
     SolvedPosition {
       position: Position {
         goal: Square::from_row_col(15, 0),
@@ -0,0 +1,111 @@
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use rand::RngCore;
+
+use crate::mechanics::BoardUnavailableError;
+use crate::mechanics::SolvedPosition;
+use crate::mechanics::SolvedPositionGenerator;
+
+// Hands out a fixed list of solved positions in order, one per call. Once the
+// list is used up the generator reports that it is exhausted, and any further
+// calls repeat the last position. Clones share their place in the list.
+#[derive(Clone, Debug)]
+pub struct PlaylistGenerator {
+  solved_positions: Arc<Vec<SolvedPosition>>,
+  next_index: Arc<AtomicUsize>,
+}
+
+impl PlaylistGenerator {
+  pub fn new(solved_positions: Vec<SolvedPosition>) -> Self {
+    Self {
+      solved_positions: Arc::new(solved_positions),
+      next_index: Arc::new(AtomicUsize::new(0)),
+    }
+  }
+
+  pub fn len(&self) -> usize {
+    self.solved_positions.len()
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.solved_positions.is_empty()
+  }
+
+  pub fn remaining(&self) -> usize {
+    self
+      .len()
+      .saturating_sub(self.next_index.load(Ordering::Relaxed))
+  }
+}
+
+impl SolvedPositionGenerator for PlaylistGenerator {
+  fn generate_solved_position_with_rng(
+    &self,
+    rng: &mut dyn RngCore,
+  ) -> SolvedPosition {
+    self
+      .try_generate_solved_position_with_rng(rng)
+      .expect("Playlist is empty")
+  }
+
+  fn try_generate_solved_position_with_rng(
+    &self,
+    _rng: &mut dyn RngCore,
+  ) -> Result<SolvedPosition, BoardUnavailableError> {
+    let index = self
+      .next_index
+      .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |index| {
+        (index < self.len()).then_some(index + 1)
+      })
+      .unwrap_or(self.len().saturating_sub(1));
+    self
+      .solved_positions
+      .get(index)
+      .cloned()
+      .ok_or(BoardUnavailableError)
+  }
+
+  fn is_exhausted(&self) -> bool {
+    self.remaining() == 0
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::board_generators::OneMoveSolutionBoardGenerator;
+
+  #[test]
+  fn plays_in_order_then_exhausts() {
+    let source = OneMoveSolutionBoardGenerator::new();
+    let solved_positions: Vec<SolvedPosition> = (0..3)
+      .map(|seed| source.generate_solved_position_from_seed(seed))
+      .collect();
+    let generator = PlaylistGenerator::new(solved_positions.clone());
+    let clone = generator.clone();
+
+    for (index, expected) in solved_positions.iter().enumerate() {
+      assert!(!generator.is_exhausted());
+      assert_eq!(generator.remaining(), 3 - index);
+      let source = if index % 2 == 0 { &generator } else { &clone };
+      assert_eq!(
+        source.generate_solved_position().position,
+        expected.position
+      );
+    }
+    assert!(generator.is_exhausted());
+    assert!(clone.is_exhausted());
+    assert_eq!(
+      generator.generate_solved_position().position,
+      solved_positions[2].position
+    );
+  }
+
+  #[test]
+  fn empty_playlist_has_no_board() {
+    let generator = PlaylistGenerator::new(Vec::new());
+    assert!(generator.try_generate_solved_position().is_err());
+  }
+}
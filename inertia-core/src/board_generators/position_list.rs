@@ -0,0 +1,40 @@
+use rand::seq::SliceRandom;
+use rand::RngCore;
+
+use crate::mechanics::BoardUnavailableError;
+use crate::mechanics::SolvedPosition;
+use crate::mechanics::SolvedPositionGenerator;
+
+// Picks at random from a fixed list of solved positions
+#[derive(Clone, Debug)]
+pub struct PositionListGenerator {
+  solved_positions: Vec<SolvedPosition>,
+}
+
+impl PositionListGenerator {
+  pub fn new(solved_positions: Vec<SolvedPosition>) -> Self {
+    Self { solved_positions }
+  }
+}
+
+impl SolvedPositionGenerator for PositionListGenerator {
+  fn generate_solved_position_with_rng(
+    &self,
+    rng: &mut dyn RngCore,
+  ) -> SolvedPosition {
+    self
+      .try_generate_solved_position_with_rng(rng)
+      .expect("Position list is empty")
+  }
+
+  fn try_generate_solved_position_with_rng(
+    &self,
+    rng: &mut dyn RngCore,
+  ) -> Result<SolvedPosition, BoardUnavailableError> {
+    self
+      .solved_positions
+      .choose(rng)
+      .cloned()
+      .ok_or(BoardUnavailableError)
+  }
+}
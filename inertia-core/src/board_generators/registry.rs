@@ -0,0 +1,524 @@
+use std::fmt;
+use std::sync::Arc;
+
+use serde::Deserialize;
+use serde::Serialize;
+use thiserror::Error;
+
+use super::ClassicBoardGenerator;
+use super::ClassicGeneratorConfig;
+use super::EmptyMiddleGoalBoardGenerator;
+use super::OneMoveSolutionBoardGenerator;
+use super::PlaylistGenerator;
+use super::PositionListGenerator;
+use super::SeededGenerator;
+use super::SolvingGenerator;
+use crate::mechanics::B64EncodedCompressedPosition;
+use crate::mechanics::CompressedPosition;
+use crate::mechanics::Position;
+use crate::mechanics::PositionGenerator;
+use crate::mechanics::SolvedPosition;
+use crate::mechanics::SolvedPositionGenerator;
+use crate::solvers::astar::try_solve_position_with_limits;
+use crate::solvers::B64EncodedCompressedSolution;
+use crate::solvers::CompressedSolution;
+use crate::solvers::Difficulty;
+use crate::solvers::DifficultyRange;
+use crate::solvers::InvertedDifficultyRangeError;
+use crate::solvers::NoSolutionReason;
+use crate::solvers::Solution;
+use crate::solvers::SolveStats;
+
+#[cfg(feature = "web")]
+use {tsify::Tsify, wasm_bindgen::prelude::wasm_bindgen};
+
+// The most moves to search when solving positions given in a spec. Specs come
+// from clients, and the hardest boards in the database take under 20 moves.
+const MAX_SPEC_SOLVE_DEPTH: usize = 30;
+// The most arrangements to expand solving any one position from a spec, about
+// twice what the hardest boards in the database need
+const MAX_POSITION_SOLVE_NODES: usize = 2_000_000;
+// The most arrangements to expand solving every position in a spec together
+const MAX_SPEC_SOLVE_NODES: usize = 20_000_000;
+// The empty middle board never changes, and takes 41 moves and a long search
+// to solve, so its optimal solution is kept rather than found each time
+const EMPTY_MIDDLE_SOLUTION: &str = "KQC9NyxW8-Si8X0lMPF87Ulgtaj57A0";
+// How many boards a classic spec draws before giving up on a round
+const MAX_CLASSIC_ATTEMPTS: usize = 20;
+// The most positions a spec can list, since each may have to be solved
+pub const MAX_SPEC_POSITIONS: usize = 100;
+
+// A description of how a room gets its positions, which can be sent by
+// clients and shown back to them
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "web", derive(Tsify), tsify(into_wasm_abi, from_wasm_abi))]
+#[serde(tag = "type", content = "content")]
+pub enum GeneratorSpec {
+  // Both ends of the range are included
+  DbByDifficulty {
+    min_difficulty: Difficulty,
+    max_difficulty: Difficulty,
+  },
+  Classic(ClassicGeneratorConfig),
+  EmptyMiddle,
+  OneMove,
+  Seeded {
+    seed: u64,
+    spec: Box<GeneratorSpec>,
+  },
+  PositionList {
+    positions: Vec<B64EncodedCompressedPosition>,
+  },
+  Playlist {
+    entries: Vec<PlaylistEntry>,
+  },
+}
+
+// A position to play in order, optionally with a known optimal solution so
+// that it doesn't need to be solved when the room is made
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(feature = "web", derive(Tsify), tsify(into_wasm_abi, from_wasm_abi))]
+pub struct PlaylistEntry {
+  pub position: B64EncodedCompressedPosition,
+  pub solution: Option<B64EncodedCompressedSolution>,
+}
+
+// What clients are shown of a spec. Position lists and playlists hold the
+// upcoming boards and their solutions, so only their lengths are given.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[cfg_attr(feature = "web", derive(Tsify), tsify(into_wasm_abi))]
+#[serde(tag = "type", content = "content")]
+pub enum GeneratorSummary {
+  DbByDifficulty {
+    min_difficulty: Difficulty,
+    max_difficulty: Difficulty,
+  },
+  Classic(ClassicGeneratorConfig),
+  EmptyMiddle,
+  OneMove,
+  Seeded {
+    seed: u64,
+    spec: Box<GeneratorSummary>,
+  },
+  PositionList {
+    length: usize,
+  },
+  Playlist {
+    length: usize,
+  },
+}
+
+impl From<&GeneratorSpec> for GeneratorSummary {
+  fn from(spec: &GeneratorSpec) -> Self {
+    match spec {
+      GeneratorSpec::DbByDifficulty {
+        min_difficulty,
+        max_difficulty,
+      } => GeneratorSummary::DbByDifficulty {
+        min_difficulty: *min_difficulty,
+        max_difficulty: *max_difficulty,
+      },
+      GeneratorSpec::Classic(config) => {
+        GeneratorSummary::Classic(config.clone())
+      }
+      GeneratorSpec::EmptyMiddle => GeneratorSummary::EmptyMiddle,
+      GeneratorSpec::OneMove => GeneratorSummary::OneMove,
+      GeneratorSpec::Seeded { seed, spec } => GeneratorSummary::Seeded {
+        seed: *seed,
+        spec: Box::new(GeneratorSummary::from(spec.as_ref())),
+      },
+      GeneratorSpec::PositionList { positions } => {
+        GeneratorSummary::PositionList {
+          length: positions.len(),
+        }
+      }
+      GeneratorSpec::Playlist { entries } => GeneratorSummary::Playlist {
+        length: entries.len(),
+      },
+    }
+  }
+}
+
+impl GeneratorSpec {
+  // Whether boards from the spec come only from the rng they're drawn with, so
+  // that a seed picks out the same boards every time. Database boards are
+  // picked ahead of time by the database generator itself.
+  pub fn is_seedable(&self) -> bool {
+    match self {
+      GeneratorSpec::DbByDifficulty { .. } => false,
+      GeneratorSpec::Seeded { spec, .. } => spec.is_seedable(),
+      _ => true,
+    }
+  }
+}
+
+impl Default for GeneratorSpec {
+  fn default() -> Self {
+    GeneratorSpec::DbByDifficulty {
+      min_difficulty: Difficulty::Easiest,
+      max_difficulty: Difficulty::Hard,
+    }
+  }
+}
+
+#[derive(Error, Debug)]
+pub enum GeneratorSpecError {
+  #[error("No position database is available")]
+  NoDbGenerator,
+  #[error("Boards from the position database can't be seeded")]
+  UnseedableSpec,
+  #[error("Position list is empty")]
+  EmptyPositionList,
+  #[error(
+    "Position list has {} positions, more than the {} allowed",
+    .0,
+    MAX_SPEC_POSITIONS
+  )]
+  TooManyPositions(usize),
+  #[error("Position {0} in the list could not be decoded")]
+  InvalidPosition(usize),
+  #[error("Position {0} in the list has no solution")]
+  UnsolvablePosition(usize),
+  #[error("Stored solution {0} in the list does not solve its position")]
+  InvalidSolution(usize),
+  #[error("Position {0} in the list took too long to solve")]
+  SolveLimitReached(usize),
+  #[error("Building the generator took too long")]
+  BuildTimedOut,
+  #[error(transparent)]
+  InvertedDifficultyRange(#[from] InvertedDifficultyRangeError),
+}
+
+pub type DbGeneratorFactory = Arc<
+  dyn Fn(DifficultyRange) -> Box<dyn SolvedPositionGenerator> + Send + Sync,
+>;
+
+// Builds generators from specs. Generators that need outside resources, like
+// the position database, are provided by whoever owns those resources.
+#[derive(Clone, Default)]
+pub struct GeneratorRegistry {
+  db_generator_factory: Option<DbGeneratorFactory>,
+}
+
+impl fmt::Debug for GeneratorRegistry {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.debug_struct("GeneratorRegistry")
+      .field("has_db_generator", &self.db_generator_factory.is_some())
+      .finish()
+  }
+}
+
+impl GeneratorRegistry {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  pub fn with_db_generator<F>(mut self, factory: F) -> Self
+  where
+    F: Fn(DifficultyRange) -> Box<dyn SolvedPositionGenerator>
+      + Send
+      + Sync
+      + 'static,
+  {
+    self.db_generator_factory = Some(Arc::new(factory));
+    self
+  }
+
+  pub fn build(
+    &self,
+    spec: &GeneratorSpec,
+  ) -> Result<Box<dyn SolvedPositionGenerator>, GeneratorSpecError> {
+    Ok(match spec {
+      GeneratorSpec::DbByDifficulty {
+        min_difficulty,
+        max_difficulty,
+      } => {
+        let range = DifficultyRange::new(*min_difficulty, *max_difficulty)?;
+        let factory = self
+          .db_generator_factory
+          .as_ref()
+          .ok_or(GeneratorSpecError::NoDbGenerator)?;
+        factory(range)
+      }
+      GeneratorSpec::Classic(config) => Box::new(
+        SolvingGenerator::new(Box::new(ClassicBoardGenerator::with_config(
+          config.clone(),
+        )))
+        .with_limits(MAX_SPEC_SOLVE_DEPTH, MAX_CLASSIC_ATTEMPTS)
+        .with_node_limit(MAX_POSITION_SOLVE_NODES),
+      ),
+      GeneratorSpec::EmptyMiddle => {
+        Box::new(PositionListGenerator::new(vec![solve_empty_middle()?]))
+      }
+      GeneratorSpec::OneMove => Box::new(OneMoveSolutionBoardGenerator::new()),
+      GeneratorSpec::Seeded { seed, spec } => {
+        if !spec.is_seedable() {
+          return Err(GeneratorSpecError::UnseedableSpec);
+        }
+        Box::new(SeededGenerator::new(self.build(spec)?, *seed))
+      }
+      GeneratorSpec::PositionList { positions } => {
+        Box::new(PositionListGenerator::new(solve_position_list(positions)?))
+      }
+      GeneratorSpec::Playlist { entries } => {
+        Box::new(PlaylistGenerator::new(solve_playlist(entries)?))
+      }
+    })
+  }
+}
+
+fn solve_empty_middle() -> Result<SolvedPosition, GeneratorSpecError> {
+  let position = EmptyMiddleGoalBoardGenerator::new().generate_position();
+  let solution = decode_solution(
+    &B64EncodedCompressedSolution(EMPTY_MIDDLE_SOLUTION.to_owned()),
+    &position,
+    0,
+  )?;
+  Ok(SolvedPosition { position, solution })
+}
+
+pub fn solve_position_list(
+  positions: &[B64EncodedCompressedPosition],
+) -> Result<Vec<SolvedPosition>, GeneratorSpecError> {
+  check_list_length(positions.len())?;
+  let mut stats = SolveStats::default();
+  positions
+    .iter()
+    .enumerate()
+    .map(|(index, encoded)| {
+      let position = decode_position(encoded, index)?;
+      let solution = solve_spec_position(position, index, &mut stats)?;
+      Ok(SolvedPosition { position, solution })
+    })
+    .collect()
+}
+
+// Decodes playlist entries, trusting stored solutions as optimal as long as
+// they actually solve their positions
+pub fn solve_playlist(
+  entries: &[PlaylistEntry],
+) -> Result<Vec<SolvedPosition>, GeneratorSpecError> {
+  check_list_length(entries.len())?;
+  let mut stats = SolveStats::default();
+  entries
+    .iter()
+    .enumerate()
+    .map(|(index, entry)| {
+      let position = decode_position(&entry.position, index)?;
+      let solution = match &entry.solution {
+        Some(encoded) => decode_solution(encoded, &position, index)?,
+        None => solve_spec_position(position, index, &mut stats)?,
+      };
+      Ok(SolvedPosition { position, solution })
+    })
+    .collect()
+}
+
+// Solves a position from a spec within what's left of the spec's node budget,
+// which stats keep track of across the spec's positions
+fn solve_spec_position(
+  position: Position,
+  index: usize,
+  stats: &mut SolveStats,
+) -> Result<Solution, GeneratorSpecError> {
+  let max_nodes = stats
+    .nodes_expanded
+    .saturating_add(MAX_POSITION_SOLVE_NODES)
+    .min(MAX_SPEC_SOLVE_NODES);
+  try_solve_position_with_limits(
+    position,
+    MAX_SPEC_SOLVE_DEPTH,
+    max_nodes,
+    stats,
+  )
+  .map_err(|reason| match reason {
+    NoSolutionReason::ExceedsNodeLimit => {
+      GeneratorSpecError::SolveLimitReached(index)
+    }
+    _ => GeneratorSpecError::UnsolvablePosition(index),
+  })
+}
+
+fn check_list_length(length: usize) -> Result<(), GeneratorSpecError> {
+  match length {
+    0 => Err(GeneratorSpecError::EmptyPositionList),
+    length if length > MAX_SPEC_POSITIONS => {
+      Err(GeneratorSpecError::TooManyPositions(length))
+    }
+    _ => Ok(()),
+  }
+}
+
+// Stored solutions are trusted to be optimal, but must at least solve their
+// positions
+fn decode_solution(
+  encoded: &B64EncodedCompressedSolution,
+  position: &Position,
+  index: usize,
+) -> Result<Solution, GeneratorSpecError> {
+  CompressedSolution::try_from(encoded)
+    .ok()
+    .and_then(|compressed| Solution::try_from(compressed).ok())
+    .filter(|solution| position.is_solution(solution))
+    .ok_or(GeneratorSpecError::InvalidSolution(index))
+}
+
+fn decode_position(
+  encoded: &B64EncodedCompressedPosition,
+  index: usize,
+) -> Result<Position, GeneratorSpecError> {
+  CompressedPosition::try_from(encoded)
+    .ok()
+    .and_then(|compressed| Position::try_from(compressed).ok())
+    .ok_or(GeneratorSpecError::InvalidPosition(index))
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::mechanics::PositionGenerator;
+
+  #[test]
+  fn builds_local_specs() {
+    let registry = GeneratorRegistry::new();
+    for spec in [
+      GeneratorSpec::Classic(ClassicGeneratorConfig::default()),
+      GeneratorSpec::OneMove,
+      GeneratorSpec::Seeded {
+        seed: 3,
+        spec: Box::new(GeneratorSpec::OneMove),
+      },
+    ] {
+      let SolvedPosition { position, solution } =
+        registry.build(&spec).unwrap().generate_solved_position();
+      assert!(position.is_solution(&solution));
+    }
+  }
+
+  #[test]
+  fn db_spec_needs_db() {
+    let spec = GeneratorSpec::default();
+    assert!(matches!(
+      GeneratorRegistry::new().build(&spec),
+      Err(GeneratorSpecError::NoDbGenerator)
+    ));
+    let registry = GeneratorRegistry::new()
+      .with_db_generator(|_| Box::new(OneMoveSolutionBoardGenerator::new()));
+    assert!(registry.build(&spec).is_ok());
+
+    let inverted_spec = GeneratorSpec::DbByDifficulty {
+      min_difficulty: Difficulty::Hard,
+      max_difficulty: Difficulty::Easy,
+    };
+    assert!(matches!(
+      registry.build(&inverted_spec),
+      Err(GeneratorSpecError::InvertedDifficultyRange(_))
+    ));
+
+    let seeded_spec = GeneratorSpec::Seeded {
+      seed: 3,
+      spec: Box::new(spec),
+    };
+    assert!(matches!(
+      registry.build(&seeded_spec),
+      Err(GeneratorSpecError::UnseedableSpec)
+    ));
+  }
+
+  #[test]
+  fn builds_empty_middle() {
+    let SolvedPosition { position, solution } = GeneratorRegistry::new()
+      .build(&GeneratorSpec::EmptyMiddle)
+      .unwrap()
+      .generate_solved_position();
+    assert!(position.is_solution(&solution));
+  }
+
+  #[test]
+  fn spec_solving_is_bounded() {
+    let position = OneMoveSolutionBoardGenerator::new().generate_position();
+    // A spec that has used up its budget stops before searching further
+    let mut stats = SolveStats {
+      nodes_expanded: MAX_SPEC_SOLVE_NODES,
+      ..SolveStats::default()
+    };
+    assert!(matches!(
+      solve_spec_position(position, 3, &mut stats),
+      Err(GeneratorSpecError::SolveLimitReached(3))
+    ));
+    let mut stats = SolveStats::default();
+    assert!(solve_spec_position(position, 0, &mut stats).is_ok());
+  }
+
+  #[test]
+  fn position_list_round_trip() {
+    let position = OneMoveSolutionBoardGenerator::new().generate_position();
+    let encoded =
+      B64EncodedCompressedPosition::from(CompressedPosition::from(position));
+    let spec = GeneratorSpec::PositionList {
+      positions: vec![encoded],
+    };
+    let generator = GeneratorRegistry::new().build(&spec).unwrap();
+    assert_eq!(generator.generate_solved_position().position, position);
+
+    let bad_spec = GeneratorSpec::PositionList {
+      positions: vec![B64EncodedCompressedPosition("nope".to_owned())],
+    };
+    assert!(matches!(
+      GeneratorRegistry::new().build(&bad_spec),
+      Err(GeneratorSpecError::InvalidPosition(0))
+    ));
+
+    // Too long a list is refused before anything in it is solved
+    let long_spec = GeneratorSpec::PositionList {
+      positions: vec![
+        B64EncodedCompressedPosition("nope".to_owned());
+        MAX_SPEC_POSITIONS + 1
+      ],
+    };
+    assert!(matches!(
+      GeneratorRegistry::new().build(&long_spec),
+      Err(GeneratorSpecError::TooManyPositions(_))
+    ));
+  }
+
+  #[test]
+  fn playlist_uses_stored_solutions() {
+    let source = OneMoveSolutionBoardGenerator::new();
+    let first = source.generate_solved_position_from_seed(0);
+    let second = source.generate_solved_position_from_seed(1);
+    let entries = vec![
+      PlaylistEntry {
+        position: CompressedPosition::from(first.position).into(),
+        solution: Some(CompressedSolution::from(&first.solution).into()),
+      },
+      PlaylistEntry {
+        position: CompressedPosition::from(second.position).into(),
+        solution: None,
+      },
+    ];
+    let generator = GeneratorRegistry::new()
+      .build(&GeneratorSpec::Playlist {
+        entries: entries.clone(),
+      })
+      .unwrap();
+    assert_eq!(
+      generator.generate_solved_position().solution,
+      first.solution
+    );
+    assert!(!generator.is_exhausted());
+    assert_eq!(
+      generator.generate_solved_position().position,
+      second.position
+    );
+    assert!(generator.is_exhausted());
+
+    let mut wrong_entries = entries;
+    wrong_entries[1].solution =
+      Some(CompressedSolution::from(Solution(vec![])).into());
+    assert!(matches!(
+      solve_playlist(&wrong_entries),
+      Err(GeneratorSpecError::InvalidSolution(1))
+    ));
+  }
+}
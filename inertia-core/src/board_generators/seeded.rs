@@ -0,0 +1,87 @@
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use rand::rngs::StdRng;
+use rand::RngCore;
+use rand::SeedableRng;
+
+use crate::mechanics::BoardUnavailableError;
+use crate::mechanics::SolvedPosition;
+use crate::mechanics::SolvedPositionGenerator;
+
+// Replays the same sequence of positions from an inner generator for a given
+// seed, ignoring the rng it is handed. Clones share their place in the
+// sequence.
+#[derive(Clone, Debug)]
+pub struct SeededGenerator {
+  inner: Box<dyn SolvedPositionGenerator>,
+  seed: u64,
+  next_index: Arc<AtomicU64>,
+}
+
+impl SeededGenerator {
+  pub fn new(inner: Box<dyn SolvedPositionGenerator>, seed: u64) -> Self {
+    Self {
+      inner,
+      seed,
+      next_index: Arc::new(AtomicU64::new(0)),
+    }
+  }
+
+  fn next_rng(&self) -> StdRng {
+    let index = self.next_index.fetch_add(1, Ordering::Relaxed);
+    StdRng::seed_from_u64(self.seed.wrapping_add(index))
+  }
+}
+
+impl SolvedPositionGenerator for SeededGenerator {
+  fn generate_solved_position_with_rng(
+    &self,
+    _rng: &mut dyn RngCore,
+  ) -> SolvedPosition {
+    self
+      .inner
+      .generate_solved_position_with_rng(&mut self.next_rng())
+  }
+
+  fn try_generate_solved_position_with_rng(
+    &self,
+    _rng: &mut dyn RngCore,
+  ) -> Result<SolvedPosition, BoardUnavailableError> {
+    self
+      .inner
+      .try_generate_solved_position_with_rng(&mut self.next_rng())
+  }
+
+  fn is_exhausted(&self) -> bool {
+    self.inner.is_exhausted()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::board_generators::ClassicBoardGenerator;
+  use crate::board_generators::SolvingGenerator;
+  use crate::test_support::UnavailableGenerator;
+
+  #[test]
+  fn same_seed_same_sequence() {
+    let inner = SolvingGenerator::new(Box::new(ClassicBoardGenerator::new()));
+    let first = SeededGenerator::new(Box::new(inner.clone()), 7);
+    let second = SeededGenerator::new(Box::new(inner), 7);
+    for _ in 0..3 {
+      assert_eq!(
+        first.generate_solved_position().position,
+        second.generate_solved_position().position
+      );
+    }
+  }
+
+  #[test]
+  fn passes_on_unavailable_boards() {
+    let generator = SeededGenerator::new(Box::new(UnavailableGenerator), 7);
+    assert!(generator.try_generate_solved_position().is_err());
+  }
+}
@@ -0,0 +1,98 @@
+use rand::RngCore;
+
+use crate::mechanics::BoardUnavailableError;
+use crate::mechanics::PositionGenerator;
+use crate::mechanics::SolvedPosition;
+use crate::mechanics::SolvedPositionGenerator;
+use crate::solvers::astar::try_solve_position_with_limits;
+use crate::solvers::SolveStats;
+
+// Turns a PositionGenerator into a SolvedPositionGenerator by solving each
+// position it makes. Positions with no solution within max_depth are thrown
+// away and drawn again, up to max_attempts times, and every attempt for one
+// position together expands at most max_nodes arrangements.
+#[derive(Clone, Debug)]
+pub struct SolvingGenerator {
+  inner: Box<dyn PositionGenerator>,
+  max_depth: usize,
+  max_attempts: usize,
+  max_nodes: usize,
+}
+
+impl SolvingGenerator {
+  pub fn new(inner: Box<dyn PositionGenerator>) -> Self {
+    Self {
+      inner,
+      max_depth: 45,
+      max_attempts: 20,
+      max_nodes: usize::MAX,
+    }
+  }
+
+  pub fn with_limits(mut self, max_depth: usize, max_attempts: usize) -> Self {
+    self.max_depth = max_depth;
+    self.max_attempts = max_attempts.max(1);
+    self
+  }
+
+  pub fn with_node_limit(mut self, max_nodes: usize) -> Self {
+    self.max_nodes = max_nodes;
+    self
+  }
+
+  fn solve_next(&self, rng: &mut dyn RngCore) -> Option<SolvedPosition> {
+    let mut stats = SolveStats::default();
+    (0..self.max_attempts).find_map(|_| {
+      let position = self.inner.generate_position_with_rng(rng);
+      try_solve_position_with_limits(
+        position,
+        self.max_depth,
+        self.max_nodes,
+        &mut stats,
+      )
+      .ok()
+      .map(|solution| SolvedPosition { position, solution })
+    })
+  }
+}
+
+impl SolvedPositionGenerator for SolvingGenerator {
+  fn generate_solved_position_with_rng(
+    &self,
+    rng: &mut dyn RngCore,
+  ) -> SolvedPosition {
+    self
+      .solve_next(rng)
+      .expect("No position could be solved within the limits")
+  }
+
+  fn try_generate_solved_position_with_rng(
+    &self,
+    rng: &mut dyn RngCore,
+  ) -> Result<SolvedPosition, BoardUnavailableError> {
+    self.solve_next(rng).ok_or(BoardUnavailableError)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::board_generators::OneMoveSolutionBoardGenerator;
+
+  #[test]
+  fn reports_unsolvable_positions() {
+    let generator =
+      SolvingGenerator::new(Box::new(OneMoveSolutionBoardGenerator::new()));
+    let SolvedPosition { position, solution } =
+      generator.try_generate_solved_position().unwrap();
+    assert!(position.is_solution(&solution));
+
+    // Nothing can be solved without any moves
+    let generator = generator.with_limits(0, 2);
+    assert!(generator.try_generate_solved_position().is_err());
+
+    // Nor without searching at all
+    let generator = generator.with_limits(10, 2).with_node_limit(0);
+    assert!(generator.try_generate_solved_position().is_err());
+  }
+}
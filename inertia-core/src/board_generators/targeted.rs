@@ -0,0 +1,390 @@
+use std::ops::RangeInclusive;
+
+use rand::seq::IteratorRandom;
+use rand::seq::SliceRandom;
+use rand::RngCore;
+use serde::Deserialize;
+use serde::Serialize;
+use thiserror::Error;
+
+use super::classic::CENTRAL_BOX;
+use super::ClassicBoardGenerator;
+use crate::mechanics::BoardUnavailableError;
+use crate::mechanics::MoveBoard;
+use crate::mechanics::Position;
+use crate::mechanics::PositionGenerator;
+use crate::mechanics::SolvedPosition;
+use crate::mechanics::SolvedPositionGenerator;
+use crate::mechanics::Square;
+use crate::solvers::astar::try_solve_with_limits;
+use crate::solvers::get_solution_difficulty;
+use crate::solvers::solve_all_goals;
+use crate::solvers::Difficulty;
+use crate::solvers::HeuristicValue;
+use crate::solvers::SolveStats;
+
+// Distance maps get much more expensive with depth, so they are only built this
+// deep. Squares further away are only known to be beyond it.
+const MAX_DISTANCE_MAP_DEPTH: usize = 8;
+// The most goals to solve for each actor arrangement
+const MAX_GOALS_PER_STEP: usize = 4;
+// The most arrangements every solve in one search expands together, a few
+// times what the hardest boards in the position database take to solve
+const DEFAULT_MAX_NODES: usize = 5_000_000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "content")]
+pub enum GenerationTarget {
+  SolutionLength(usize),
+  Difficulty(Difficulty),
+}
+
+impl GenerationTarget {
+  // Optimal solution lengths worth trying for this target. For a difficulty
+  // these are the lengths where the difficulty rating commonly lands.
+  pub fn solution_lengths(&self) -> RangeInclusive<usize> {
+    match self {
+      GenerationTarget::SolutionLength(length) => *length..=*length,
+      GenerationTarget::Difficulty(difficulty) => match difficulty {
+        Difficulty::Easiest => 1..=4,
+        Difficulty::Easy => 3..=7,
+        Difficulty::Medium => 5..=12,
+        Difficulty::Hard => 8..=15,
+        Difficulty::Hardest => 10..=20,
+      },
+    }
+  }
+
+  pub fn validate(&self) -> Result<(), ZeroSolutionLengthError> {
+    match self {
+      GenerationTarget::SolutionLength(0) => Err(ZeroSolutionLengthError),
+      _ => Ok(()),
+    }
+  }
+
+  pub fn is_met_by(&self, solved_position: &SolvedPosition) -> bool {
+    let solution = &solved_position.solution;
+    match self {
+      GenerationTarget::SolutionLength(length) => solution.0.len() == *length,
+      GenerationTarget::Difficulty(difficulty) => {
+        get_solution_difficulty(solution) == *difficulty
+      }
+    }
+  }
+}
+
+#[derive(Error, Debug)]
+#[error("Target solution length has to be at least one move")]
+pub struct ZeroSolutionLengthError;
+
+// Produces positions for a target solution length or difficulty by search
+// rather than rejection sampling. Walls and actors come from a base
+// generator. The distance from the actors to every square is then used to pick
+// goals at about the right distance, which are solved to check the target.
+// While there are none, actors are moved one at a time as long as that does
+// not take the goals further from the target distance. If nothing meets the
+// target within the search limits, the nearest miss is used instead. Goals
+// past the distance map can't be told apart, so deep targets lean on solving,
+// and all the solving for one search shares a budget of max_nodes expanded
+// arrangements. Solving the nearest miss gets a budget of its own.
+#[derive(Clone, Debug)]
+pub struct TargetedBoardGenerator {
+  base_generator: Box<dyn PositionGenerator>,
+  target: GenerationTarget,
+  max_boards: usize,
+  climb_steps: usize,
+  max_nodes: usize,
+}
+
+impl TargetedBoardGenerator {
+  pub fn new(
+    target: GenerationTarget,
+  ) -> Result<Self, ZeroSolutionLengthError> {
+    Self::with_base_generator(target, Box::new(ClassicBoardGenerator::new()))
+  }
+
+  pub fn with_base_generator(
+    target: GenerationTarget,
+    base_generator: Box<dyn PositionGenerator>,
+  ) -> Result<Self, ZeroSolutionLengthError> {
+    target.validate()?;
+    Ok(Self {
+      base_generator,
+      target,
+      max_boards: 50,
+      climb_steps: 30,
+      max_nodes: DEFAULT_MAX_NODES,
+    })
+  }
+
+  pub fn with_search_limits(
+    mut self,
+    max_boards: usize,
+    climb_steps: usize,
+  ) -> Self {
+    self.max_boards = max_boards.max(1);
+    self.climb_steps = climb_steps;
+    self
+  }
+
+  pub fn with_node_limit(mut self, max_nodes: usize) -> Self {
+    self.max_nodes = max_nodes;
+    self
+  }
+
+  pub fn target(&self) -> GenerationTarget {
+    self.target
+  }
+
+  // Solves goals in order until one meets the target or the budget in stats
+  // runs out
+  fn solve_first_goal(
+    &self,
+    board: &MoveBoard,
+    position: &Position,
+    goals: &[Square],
+    require_target: bool,
+    stats: &mut SolveStats,
+  ) -> Option<SolvedPosition> {
+    let max_depth = *self.target.solution_lengths().end();
+    goals.iter().take(MAX_GOALS_PER_STEP).find_map(|&goal| {
+      let solution = try_solve_with_limits(
+        board,
+        goal,
+        position.actor_squares,
+        max_depth,
+        self.max_nodes,
+        stats,
+      )
+      .ok()?;
+      let solved_position = SolvedPosition {
+        position: Position { goal, ..*position },
+        solution,
+      };
+      (!require_target || self.target.is_met_by(&solved_position))
+        .then_some(solved_position)
+    })
+  }
+}
+
+// The goals closest to the target lengths, along with how far they are from
+// them. Goals are corners outside the central box that no actor is on. Goals
+// beyond the distance map are taken to be just past it, since they may well be
+// in range.
+fn closest_goals(
+  board: &MoveBoard,
+  position: &Position,
+  lengths: &RangeInclusive<usize>,
+) -> (Vec<Square>, usize) {
+  let depth = (*lengths.end()).min(MAX_DISTANCE_MAP_DEPTH);
+  let distances = solve_all_goals(board, position.actor_squares, depth);
+  let mut goals = Vec::new();
+  let mut gap = usize::MAX;
+  for square in (0..=255).map(Square) {
+    let distance = match distances[square.0 as usize] {
+      HeuristicValue::MAX if depth < *lengths.end() => depth + 1,
+      HeuristicValue::MAX => continue,
+      distance => distance as usize,
+    };
+    if CENTRAL_BOX.contains(square)
+      || position.actor_squares.0.contains(&square)
+      || !position
+        .walled_board
+        .walls_for_square(square, true)
+        .is_corner()
+    {
+      continue;
+    }
+    let square_gap = (*lengths.start()).min(depth + 1).saturating_sub(distance);
+    if square_gap < gap {
+      goals.clear();
+      gap = square_gap;
+    }
+    if square_gap == gap {
+      goals.push(square);
+    }
+  }
+  (goals, gap)
+}
+
+impl TargetedBoardGenerator {
+  // Searches up to max_boards base boards, returning None only if not even a
+  // near miss could be solved
+  fn search(&self, rng: &mut dyn RngCore) -> Option<SolvedPosition> {
+    let lengths = self.target.solution_lengths();
+    let mut closest: Option<(usize, MoveBoard, Position, Vec<Square>)> = None;
+    let mut stats = SolveStats::default();
+
+    for _ in 0..self.max_boards {
+      if stats.nodes_expanded >= self.max_nodes {
+        break;
+      }
+      let mut position = self.base_generator.generate_position_with_rng(rng);
+      let board = MoveBoard::from(&position.walled_board);
+      let (mut goals, mut gap) = closest_goals(&board, &position, &lengths);
+
+      for _ in 0..=self.climb_steps {
+        if stats.nodes_expanded >= self.max_nodes {
+          break;
+        }
+        if gap == 0 {
+          goals.shuffle(rng);
+          if let Some(solved_position) =
+            self.solve_first_goal(&board, &position, &goals, true, &mut stats)
+          {
+            return Some(solved_position);
+          }
+        }
+
+        // Move a random actor to a random empty square outside the central
+        // box, keeping the move unless it takes the goals further from the
+        // target lengths
+        let actor_index = (0..4).choose(rng).expect("There are four actors");
+        let square = (0..=255)
+          .map(Square)
+          .filter(|&square| {
+            !CENTRAL_BOX.contains(square)
+              && !position.actor_squares.0.contains(&square)
+          })
+          .choose(rng)
+          .expect("There are more empty squares than actors");
+        let mut next_position = position;
+        next_position.actor_squares.0[actor_index] = square;
+        let (next_goals, next_gap) =
+          closest_goals(&board, &next_position, &lengths);
+        // Moves that keep the same gap are taken too, so that the search can
+        // wander across plateaus and past goals that missed a difficulty
+        if next_gap <= gap {
+          position = next_position;
+          goals = next_goals;
+          gap = next_gap;
+        }
+      }
+
+      let is_closer = closest
+        .as_ref()
+        .is_none_or(|(closest_gap, ..)| gap < *closest_gap);
+      if is_closer && !goals.is_empty() {
+        closest = Some((gap, board, position, goals));
+      }
+    }
+
+    // Nothing met the target within the search limits, so settle for the
+    // nearest miss
+    let (_, board, position, goals) = closest?;
+    self.solve_first_goal(
+      &board,
+      &position,
+      &goals,
+      false,
+      &mut SolveStats::default(),
+    )
+  }
+}
+
+impl SolvedPositionGenerator for TargetedBoardGenerator {
+  fn generate_solved_position_with_rng(
+    &self,
+    rng: &mut dyn RngCore,
+  ) -> SolvedPosition {
+    self
+      .search(rng)
+      .expect("Targeted search found no board, not even a near miss")
+  }
+
+  fn try_generate_solved_position_with_rng(
+    &self,
+    rng: &mut dyn RngCore,
+  ) -> Result<SolvedPosition, BoardUnavailableError> {
+    self.search(rng).ok_or(BoardUnavailableError)
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::board_generators::EmptyMiddleGoalBoardGenerator;
+  use crate::solvers::astar::solve_position;
+  use rand::rngs::StdRng;
+  use rand::SeedableRng;
+
+  #[test]
+  fn generates_target_length() {
+    for length in [3, 5] {
+      let generator =
+        TargetedBoardGenerator::new(GenerationTarget::SolutionLength(length))
+          .unwrap();
+      for seed in 0..3 {
+        let SolvedPosition { position, solution } =
+          generator.generate_solved_position_from_seed(seed);
+        assert_eq!(solution.0.len(), length);
+        assert!(position.is_solution(&solution));
+        assert_eq!(solve_position(position, length).unwrap().0.len(), length);
+      }
+    }
+  }
+
+  #[test]
+  fn generates_target_difficulty() {
+    let target = GenerationTarget::Difficulty(Difficulty::Easy);
+    let generator = TargetedBoardGenerator::new(target).unwrap();
+    for seed in 0..3 {
+      let solved_position = generator.generate_solved_position_from_seed(seed);
+      assert!(target.is_met_by(&solved_position));
+    }
+  }
+
+  #[test]
+  fn falls_back_to_nearest_miss() {
+    // No base board search is allowed, so the first board is returned with its
+    // closest goal
+    let generator =
+      TargetedBoardGenerator::new(GenerationTarget::SolutionLength(6))
+        .unwrap()
+        .with_search_limits(1, 0);
+    let SolvedPosition { position, solution } =
+      generator.generate_solved_position_from_seed(0);
+    assert!(position.is_solution(&solution));
+    assert!(solution.0.len() <= 6);
+  }
+
+  #[test]
+  fn rejects_zero_length() {
+    assert!(
+      TargetedBoardGenerator::new(GenerationTarget::SolutionLength(0)).is_err()
+    );
+  }
+
+  #[test]
+  fn finishes_when_target_is_out_of_reach() {
+    // Nothing on an empty board is more than a few moves away, so the search
+    // runs out and has to fall back on the nearest miss
+    let generator = TargetedBoardGenerator::with_base_generator(
+      GenerationTarget::SolutionLength(20),
+      Box::new(EmptyMiddleGoalBoardGenerator::new()),
+    )
+    .unwrap()
+    .with_search_limits(2, 2);
+    let mut rng = StdRng::seed_from_u64(0);
+    let SolvedPosition { position, solution } = generator
+      .try_generate_solved_position_with_rng(&mut rng)
+      .unwrap();
+    assert!(position.is_solution(&solution));
+    assert!(solution.0.len() < 20);
+  }
+
+  #[test]
+  fn solving_is_bounded() {
+    // Hardest boards would take many deep solves to find. With no budget the
+    // search stops straight away, and the near miss can't be solved either.
+    let generator = TargetedBoardGenerator::new(GenerationTarget::Difficulty(
+      Difficulty::Hardest,
+    ))
+    .unwrap()
+    .with_node_limit(0);
+    let mut rng = StdRng::seed_from_u64(0);
+    assert!(generator
+      .try_generate_solved_position_with_rng(&mut rng)
+      .is_err());
+  }
+}
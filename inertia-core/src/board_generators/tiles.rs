@@ -0,0 +1,333 @@
+use std::path::Path;
+use std::str::FromStr;
+
+use rand::seq::IteratorRandom;
+use rand::seq::SliceRandom;
+use rand::RngCore;
+use thiserror::Error;
+
+use super::classic::add_central_box;
+use super::classic::CENTRAL_BOX;
+use crate::mechanics::ActorSquares;
+use crate::mechanics::Position;
+use crate::mechanics::PositionGenerator;
+use crate::mechanics::Square;
+use crate::mechanics::WalledBoard;
+
+const DEFAULT_TILES: &str = include_str!("quarter_tiles.txt");
+
+const TILE_SIZE: usize = 8;
+const TILE_GRID_LINES: usize = 2 * TILE_SIZE - 1;
+const QUADRANTS: usize = 4;
+
+#[derive(Error, Debug)]
+pub enum TileParseError {
+  #[error("Line {line}: expected a tile header")]
+  ExpectedTileHeader { line: usize },
+  #[error(
+    "Tile starting on line {line} has fewer than {TILE_GRID_LINES} lines"
+  )]
+  IncompleteTile { line: usize },
+  #[error("Line {line}, column {column}: unexpected character {character:?}")]
+  UnexpectedCharacter {
+    line: usize,
+    column: usize,
+    character: char,
+  },
+  #[error("Expected at least {QUADRANTS} pieces, found {0}")]
+  NotEnoughPieces(usize),
+  #[error(transparent)]
+  Io(#[from] std::io::Error),
+}
+
+// One side of a quarter board, as it sits in the top left quadrant. Only the
+// walls and targets of the top left 8x8 squares are used.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QuarterTile {
+  pub walled_board: WalledBoard,
+  pub targets: Vec<Square>,
+}
+
+impl QuarterTile {
+  // Rotates the tile a quarter turn clockwise about the centre of the board,
+  // moving it into the next quadrant.
+  pub fn rotated_clockwise(&self) -> Self {
+    let rotate = |square: Square| {
+      let (row, col) = square.as_row_col();
+      Square::from((col, 15 - row))
+    };
+
+    let mut walled_board = WalledBoard::EMPTY;
+    for square in (0..=255).map(Square) {
+      let rotated = rotate(square);
+      if self.walled_board.get_wall_right(square, false) {
+        walled_board.set_wall_down(rotated, true);
+      }
+      if self.walled_board.get_wall_down(square, false) {
+        walled_board.set_wall_left(rotated, true);
+      }
+    }
+
+    Self {
+      walled_board,
+      targets: self.targets.iter().copied().map(rotate).collect(),
+    }
+  }
+
+  fn parse_grid(
+    grid_lines: &[&str],
+    first_line: usize,
+  ) -> Result<Self, TileParseError> {
+    let mut walled_board = WalledBoard::EMPTY;
+    let mut targets = Vec::new();
+
+    for (grid_row, grid_line) in grid_lines.iter().enumerate() {
+      let line = first_line + grid_row;
+      let row = grid_row / 2;
+      for (column, character) in grid_line.chars().enumerate() {
+        let col = column / 2;
+        if col >= TILE_SIZE {
+          if character.is_whitespace() {
+            continue;
+          }
+          return Err(TileParseError::UnexpectedCharacter {
+            line,
+            column: column + 1,
+            character,
+          });
+        }
+        let is_square_row = grid_row % 2 == 0;
+        let is_square_col = column % 2 == 0;
+        match (is_square_row, is_square_col, character) {
+          (true, true, '.') => {}
+          (true, true, 'T') => targets.push(Square::from((row, col))),
+          (true, false, '|') => walled_board.set_wall_right((row, col), true),
+          (true, false, ' ') => {}
+          (false, true, '-') => walled_board.set_wall_down((row, col), true),
+          (false, true, ' ') => {}
+          (false, false, _) => {}
+          _ => {
+            return Err(TileParseError::UnexpectedCharacter {
+              line,
+              column: column + 1,
+              character,
+            })
+          }
+        }
+      }
+    }
+
+    Ok(Self {
+      walled_board,
+      targets,
+    })
+  }
+}
+
+// Quarter tiles grouped by physical piece. Each piece has one or more sides,
+// and a board uses at most one side of each piece.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TileLibrary {
+  pieces: Vec<Vec<QuarterTile>>,
+}
+
+impl TileLibrary {
+  pub fn new(pieces: Vec<Vec<QuarterTile>>) -> Result<Self, TileParseError> {
+    let pieces: Vec<Vec<QuarterTile>> = pieces
+      .into_iter()
+      .filter(|sides| !sides.is_empty())
+      .collect();
+    if pieces.len() < QUADRANTS {
+      return Err(TileParseError::NotEnoughPieces(pieces.len()));
+    }
+    Ok(Self { pieces })
+  }
+
+  pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, TileParseError> {
+    std::fs::read_to_string(path)?.parse()
+  }
+
+  pub fn pieces(&self) -> &[Vec<QuarterTile>] {
+    &self.pieces
+  }
+}
+
+impl Default for TileLibrary {
+  fn default() -> Self {
+    DEFAULT_TILES
+      .parse()
+      .expect("Built in tile library should be valid")
+  }
+}
+
+impl FromStr for TileLibrary {
+  type Err = TileParseError;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let lines: Vec<&str> = s.lines().collect();
+    let mut piece_names: Vec<&str> = Vec::new();
+    let mut pieces: Vec<Vec<QuarterTile>> = Vec::new();
+
+    let mut index = 0;
+    while index < lines.len() {
+      let line = lines[index].trim();
+      index += 1;
+      if line.is_empty() || line.starts_with('#') {
+        continue;
+      }
+      let Some(piece_name) = line.strip_prefix("tile ").map(str::trim) else {
+        return Err(TileParseError::ExpectedTileHeader { line: index });
+      };
+      let Some(grid_lines) = lines.get(index..index + TILE_GRID_LINES) else {
+        return Err(TileParseError::IncompleteTile { line: index });
+      };
+      let tile = QuarterTile::parse_grid(grid_lines, index + 1)?;
+      index += TILE_GRID_LINES;
+
+      match piece_names.iter().position(|&name| name == piece_name) {
+        Some(piece_index) => pieces[piece_index].push(tile),
+        None => {
+          piece_names.push(piece_name);
+          pieces.push(vec![tile]);
+        }
+      }
+    }
+
+    Self::new(pieces)
+  }
+}
+
+// Builds boards like the physical game: four pieces are placed in random
+// quadrants, each showing a random side, around the central box. The goal is
+// one of the targets marked on the tiles.
+#[derive(Clone, Debug, Default)]
+pub struct TileBoardGenerator {
+  library: TileLibrary,
+}
+
+impl TileBoardGenerator {
+  pub fn new(library: TileLibrary) -> Self {
+    Self { library }
+  }
+}
+
+impl PositionGenerator for TileBoardGenerator {
+  fn generate_position_with_rng(&self, rng: &mut dyn RngCore) -> Position {
+    let mut pieces = self.library.pieces.iter().choose_multiple(rng, QUADRANTS);
+    pieces.shuffle(rng);
+
+    let mut walled_board = WalledBoard::EMPTY;
+    let mut targets = Vec::new();
+    for (quadrant, sides) in pieces.into_iter().enumerate() {
+      let mut tile = sides.choose(rng).expect("Pieces have a side").clone();
+      for _ in 0..quadrant {
+        tile = tile.rotated_clockwise();
+      }
+      merge_walls(&mut walled_board, &tile.walled_board);
+      targets.extend(tile.targets);
+    }
+    add_central_box(&mut walled_board);
+
+    let mut actor_squares: [Square; 4] = [Square(0); 4];
+    (0..=255)
+      .map(Square)
+      .filter(|&square| !CENTRAL_BOX.contains(square))
+      .choose_multiple_fill(rng, &mut actor_squares);
+    actor_squares.shuffle(rng);
+
+    let goal = targets
+      .iter()
+      .copied()
+      .filter(|square| !actor_squares.contains(square))
+      .choose(rng)
+      .or_else(|| {
+        (0..=255)
+          .map(Square)
+          .filter(|&square| {
+            !CENTRAL_BOX.contains(square) && !actor_squares.contains(&square)
+          })
+          .choose(rng)
+      })
+      .expect("There will always be a square not occupied by an actor");
+
+    Position {
+      goal,
+      actor_squares: ActorSquares(actor_squares),
+      walled_board,
+    }
+  }
+}
+
+fn merge_walls(walled_board: &mut WalledBoard, other: &WalledBoard) {
+  for (walls, other_walls) in walled_board
+    .vertical
+    .iter_mut()
+    .chain(walled_board.horizontal.iter_mut())
+    .zip(other.vertical.iter().chain(other.horizontal.iter()))
+  {
+    for (wall, &other_wall) in walls.iter_mut().zip(other_walls) {
+      *wall |= other_wall;
+    }
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn default_library_parses() {
+    let library = TileLibrary::default();
+    assert_eq!(library.pieces().len(), 4);
+    for sides in library.pieces() {
+      assert_eq!(sides.len(), 2);
+      for tile in sides {
+        assert!(!tile.targets.is_empty());
+        for target in &tile.targets {
+          assert!(tile
+            .walled_board
+            .walls_for_square(*target, false)
+            .is_corner());
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn four_rotations_are_identity() {
+    for tile in TileLibrary::default().pieces().iter().flatten() {
+      let rotated = tile
+        .rotated_clockwise()
+        .rotated_clockwise()
+        .rotated_clockwise()
+        .rotated_clockwise();
+      assert_eq!(&rotated, tile);
+    }
+  }
+
+  #[test]
+  fn rejects_incomplete_tile() {
+    let result = "tile 1\n. . . . . . . .\n".parse::<TileLibrary>();
+    assert!(matches!(
+      result,
+      Err(TileParseError::IncompleteTile { line: 1 })
+    ));
+  }
+
+  #[test]
+  fn goal_is_a_tile_target() {
+    let generator = TileBoardGenerator::default();
+    for seed in 0..20 {
+      let Position {
+        walled_board,
+        actor_squares,
+        goal,
+      } = generator.generate_position_from_seed(seed);
+      assert!(walled_board.walls_for_square(goal, false).is_corner());
+      assert!(!actor_squares.0.contains(&goal));
+      for square in actor_squares.0 {
+        assert!(!CENTRAL_BOX.contains(square));
+      }
+    }
+  }
+}
@@ -0,0 +1,85 @@
+use rand::RngCore;
+
+use crate::mechanics::BoardUnavailableError;
+use crate::mechanics::SolvedPosition;
+use crate::mechanics::SolvedPositionGenerator;
+use crate::solvers::is_solved_position_unique;
+
+// Draws from an inner generator until the optimal solution is unique up to
+// move commutation. If no unique position turns up within max_attempts, the
+// last position drawn is used anyway so that generation always finishes.
+#[derive(Clone, Debug)]
+pub struct UniqueSolutionGenerator {
+  inner: Box<dyn SolvedPositionGenerator>,
+  max_attempts: usize,
+}
+
+impl UniqueSolutionGenerator {
+  pub fn new(
+    inner: Box<dyn SolvedPositionGenerator>,
+    max_attempts: usize,
+  ) -> Self {
+    Self {
+      inner,
+      max_attempts: max_attempts.max(1),
+    }
+  }
+}
+
+impl SolvedPositionGenerator for UniqueSolutionGenerator {
+  fn generate_solved_position_with_rng(
+    &self,
+    rng: &mut dyn RngCore,
+  ) -> SolvedPosition {
+    let mut solved_position = self.inner.generate_solved_position_with_rng(rng);
+    for _ in 1..self.max_attempts {
+      if is_solved_position_unique(&solved_position) {
+        break;
+      }
+      solved_position = self.inner.generate_solved_position_with_rng(rng);
+    }
+    solved_position
+  }
+
+  fn try_generate_solved_position_with_rng(
+    &self,
+    rng: &mut dyn RngCore,
+  ) -> Result<SolvedPosition, BoardUnavailableError> {
+    let mut solved_position =
+      self.inner.try_generate_solved_position_with_rng(rng)?;
+    for _ in 1..self.max_attempts {
+      if is_solved_position_unique(&solved_position) {
+        break;
+      }
+      solved_position =
+        self.inner.try_generate_solved_position_with_rng(rng)?;
+    }
+    Ok(solved_position)
+  }
+
+  fn is_exhausted(&self) -> bool {
+    self.inner.is_exhausted()
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::board_generators::GenerationTarget;
+  use crate::board_generators::TargetedBoardGenerator;
+
+  #[test]
+  fn generates_unique_positions() {
+    let generator = UniqueSolutionGenerator::new(
+      Box::new(
+        TargetedBoardGenerator::new(GenerationTarget::SolutionLength(4))
+          .unwrap(),
+      ),
+      100,
+    );
+    for seed in 0..3 {
+      let solved_position = generator.generate_solved_position_from_seed(seed);
+      assert!(is_solved_position_unique(&solved_position));
+    }
+  }
+}
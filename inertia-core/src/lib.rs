@@ -4,5 +4,9 @@ pub mod message;
 pub mod solvers;
 pub mod state;
 
+// Generators and helpers for tests, here and in crates that depend on this one
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_support;
+
 #[cfg(feature = "web")]
 pub mod wasm;
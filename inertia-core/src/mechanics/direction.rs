@@ -11,6 +11,7 @@ use {tsify::Tsify, wasm_bindgen::prelude::wasm_bindgen};
   Clone,
   PartialEq,
   Eq,
+  Hash,
   Debug,
   Serialize,
   Deserialize,
@@ -13,6 +13,10 @@ use base64::Engine;
 use num_enum::IntoPrimitive;
 use num_enum::TryFromPrimitive;
 use num_enum::TryFromPrimitiveError;
+use rand::rngs::StdRng;
+use rand::thread_rng;
+use rand::RngCore;
+use rand::SeedableRng;
 use serde::Deserialize;
 use serde::Serialize;
 use thiserror::Error;
@@ -76,7 +80,7 @@ pub enum PositionCompressionType {
   NaiveAssumingAnyActorGoal = 0,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[cfg_attr(feature = "web", derive(Tsify), tsify(into_wasm_abi, from_wasm_abi))]
 pub struct B64EncodedCompressedPosition(pub String);
 
@@ -262,7 +266,17 @@ where
 pub trait PositionGenerator:
   CloneDynPositionGenerator + std::fmt::Debug + Send + Sync
 {
-  fn generate_position(&self) -> Position;
+  // All randomness must come from the given rng, so that a generator and a
+  // seed together always identify the same position.
+  fn generate_position_with_rng(&self, rng: &mut dyn RngCore) -> Position;
+
+  fn generate_position(&self) -> Position {
+    self.generate_position_with_rng(&mut thread_rng())
+  }
+
+  fn generate_position_from_seed(&self, seed: u64) -> Position {
+    self.generate_position_with_rng(&mut StdRng::seed_from_u64(seed))
+  }
 }
 
 impl Clone for Box<dyn PositionGenerator> {
@@ -271,6 +285,7 @@ impl Clone for Box<dyn PositionGenerator> {
   }
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SolvedPosition {
   pub position: Position,
   pub solution: Solution,
@@ -331,10 +346,50 @@ where
   }
 }
 
+#[derive(Error, Debug)]
+#[error("No board is available from the generator")]
+pub struct BoardUnavailableError;
+
 pub trait SolvedPositionGenerator:
   CloneDynSolvedPositionGenerator + std::fmt::Debug + Send + Sync
 {
-  fn generate_solved_position(&self) -> SolvedPosition;
+  // All randomness must come from the given rng, so that a generator and a
+  // seed together always identify the same solved position.
+  fn generate_solved_position_with_rng(
+    &self,
+    rng: &mut dyn RngCore,
+  ) -> SolvedPosition;
+
+  fn generate_solved_position(&self) -> SolvedPosition {
+    self.generate_solved_position_with_rng(&mut thread_rng())
+  }
+
+  fn generate_solved_position_from_seed(&self, seed: u64) -> SolvedPosition {
+    self.generate_solved_position_with_rng(&mut StdRng::seed_from_u64(seed))
+  }
+
+  // Generators that rely on outside resources or a bounded search can fail to
+  // produce a position. Those should override this to report it, and panic
+  // rather than hand out a placeholder board when drawn from without it. The
+  // server only draws through this.
+  fn try_generate_solved_position_with_rng(
+    &self,
+    rng: &mut dyn RngCore,
+  ) -> Result<SolvedPosition, BoardUnavailableError> {
+    Ok(self.generate_solved_position_with_rng(rng))
+  }
+
+  fn try_generate_solved_position(
+    &self,
+  ) -> Result<SolvedPosition, BoardUnavailableError> {
+    self.try_generate_solved_position_with_rng(&mut thread_rng())
+  }
+
+  // Whether the generator has run out of positions. Most generators never do,
+  // but ones backed by a fixed sequence can end a game once it is used up.
+  fn is_exhausted(&self) -> bool {
+    false
+  }
 }
 
 impl Clone for Box<dyn SolvedPositionGenerator> {
@@ -347,8 +402,8 @@ impl<T> PositionGenerator for T
 where
   T: 'static + SolvedPositionGenerator + Clone,
 {
-  fn generate_position(&self) -> Position {
-    self.generate_solved_position().position
+  fn generate_position_with_rng(&self, rng: &mut dyn RngCore) -> Position {
+    self.generate_solved_position_with_rng(rng).position
   }
 }
 
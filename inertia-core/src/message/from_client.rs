@@ -1,9 +1,15 @@
+use crate::board_generators::GeneratorSpec;
 use crate::solvers::difficulty::Difficulty;
 use crate::solvers::Solution;
+use crate::state::data::GameLength;
 use crate::state::data::PlayerId;
 use crate::state::data::PlayerName;
 use crate::state::data::PlayerReconnectKey;
+use crate::state::data::PlayerRole;
 use crate::state::data::RoomId;
+use crate::state::data::RoundTimers;
+use crate::state::scoring::ScoringPolicy;
+use crate::state::view::SolutionVisibility;
 use serde::Deserialize;
 use strum::Display;
 
@@ -17,7 +23,13 @@ pub enum FromClientMessage {
   ExplicitPing,
   Rename(RenameMessage),
   Join(JoinMessage),
+  SetRole(SetRoleMessage),
   StartRound,
+  KickPlayer(KickPlayerMessage),
+  TransferHost(TransferHostMessage),
+  UpdateSettings(UpdateSettingsMessage),
+  EndGame,
+  NewGame,
   Bid(BidMessage),
   ReadyBid,
   UnreadyBid,
@@ -43,6 +55,34 @@ pub struct RenameMessage {
   pub player_name: PlayerName,
 }
 
+#[derive(Deserialize, Debug)]
+#[cfg_attr(feature = "web", derive(Tsify), tsify(from_wasm_abi))]
+pub struct SetRoleMessage {
+  pub role: PlayerRole,
+}
+
+#[derive(Deserialize, Debug)]
+#[cfg_attr(feature = "web", derive(Tsify), tsify(from_wasm_abi))]
+pub struct KickPlayerMessage {
+  pub player_id: PlayerId,
+}
+
+#[derive(Deserialize, Debug)]
+#[cfg_attr(feature = "web", derive(Tsify), tsify(from_wasm_abi))]
+pub struct TransferHostMessage {
+  pub player_id: PlayerId,
+}
+
+#[derive(Deserialize, Debug)]
+#[cfg_attr(feature = "web", derive(Tsify), tsify(from_wasm_abi))]
+pub struct UpdateSettingsMessage {
+  pub generator: Option<GeneratorSpec>,
+  pub timers: Option<RoundTimers>,
+  pub scoring: Option<ScoringPolicy>,
+  pub game_length: Option<GameLength>,
+  pub solution_visibility: Option<SolutionVisibility>,
+}
+
 #[derive(Deserialize, Debug)]
 #[cfg_attr(feature = "web", derive(Tsify), tsify(from_wasm_abi))]
 pub struct JoinMessage {
@@ -52,4 +92,15 @@ pub struct JoinMessage {
   pub room_id: RoomId,
   pub min_difficulty: Option<Difficulty>,
   pub max_difficulty: Option<Difficulty>,
+  // Takes precedence over the difficulty range when set. Only used by the
+  // player who creates the room.
+  pub generator: Option<GeneratorSpec>,
+  // Defaults apply if unset. Only used by the player who creates the room.
+  pub timers: Option<RoundTimers>,
+  // Classic scoring if unset. Only used by the player who creates the room.
+  pub scoring: Option<ScoringPolicy>,
+  // Unlimited if unset. Only used by the player who creates the room.
+  pub game_length: Option<GameLength>,
+  // Joins as a player if unset
+  pub role: Option<PlayerRole>,
 }
@@ -1,4 +1,4 @@
-use crate::state::data::RoomState;
+use crate::state::view::RoomView;
 use serde::Serialize;
 
 #[cfg(feature = "web")]
@@ -8,9 +8,10 @@ use {tsify::Tsify, wasm_bindgen::prelude::wasm_bindgen};
 #[cfg_attr(feature = "web", derive(Tsify), tsify(into_wasm_abi))]
 #[serde(tag = "type", content = "content")]
 pub enum ToClientMessage {
-  RoomUpdate(Box<RoomState>),
+  RoomUpdate(Box<RoomView>),
   CountdownUpdate(CountdownUpdateMessage),
   ExplicitPong,
+  Error(ErrorMessage),
 }
 
 #[derive(Serialize, Clone, Copy, Debug)]
@@ -18,3 +19,10 @@ pub enum ToClientMessage {
 pub struct CountdownUpdateMessage {
   pub server_time_left_millis: u128,
 }
+
+// Tells a single client why something it asked for was refused
+#[derive(Serialize, Clone, Debug)]
+#[cfg_attr(feature = "web", derive(Tsify), tsify(into_wasm_abi))]
+pub struct ErrorMessage {
+  pub message: String,
+}
@@ -0,0 +1,141 @@
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::collections::VecDeque;
+
+use crate::mechanics::ActorSquares;
+use crate::mechanics::MoveBoard;
+use crate::mechanics::Position;
+use crate::solvers::roll_zobrist_hash;
+use crate::solvers::zobrist_hash;
+use crate::solvers::HeuristicValue;
+use crate::solvers::MinMovesBoard;
+use crate::solvers::NoopHasherBuilder;
+
+pub fn solve_position_all_goals<P: Borrow<Position>>(
+  position: P,
+  max_depth: usize,
+) -> [HeuristicValue; 256] {
+  let Position {
+    walled_board,
+    actor_squares,
+    ..
+  } = position.borrow();
+  let board = MoveBoard::from(walled_board);
+  solve_all_goals(&board, *actor_squares, max_depth)
+}
+
+// Breadth first search over actor arrangements, recording the depth at which
+// any actor first comes to rest on each square. Squares that are not reached
+// within max_depth are left as HeuristicValue::MAX.
+pub fn solve_all_goals(
+  board: &MoveBoard,
+  actor_squares: ActorSquares,
+  max_depth: usize,
+) -> [HeuristicValue; 256] {
+  let max_depth = max_depth.min(HeuristicValue::MAX as usize - 1);
+  let mut distances = [HeuristicValue::MAX; 256];
+
+  // A square that no actor could reach even if it were able to stop anywhere
+  // is definitely unreachable. Knowing this lets us stop searching once every
+  // other square has been found instead of exhausting the arrangement space.
+  let mut remaining = 0;
+  let mut possibly_reachable = [false; 256];
+  for actor_square in actor_squares.0 {
+    let min_moves_board = MinMovesBoard::from_move_board(board, actor_square);
+    for (index, &value) in min_moves_board.squares.iter().enumerate() {
+      if value != HeuristicValue::MAX && !possibly_reachable[index] {
+        possibly_reachable[index] = true;
+        remaining += 1;
+      }
+    }
+  }
+
+  let mut queue: VecDeque<(ActorSquares, u8)> = VecDeque::new();
+  let mut visited: HashSet<u64, NoopHasherBuilder> =
+    HashSet::with_capacity_and_hasher(1024, NoopHasherBuilder::default());
+
+  queue.push_back((actor_squares, 0));
+  visited.insert(zobrist_hash(actor_squares.as_bytes()));
+
+  while let Some((actor_squares, depth)) = queue.pop_front() {
+    for square in actor_squares.0 {
+      let distance = &mut distances[square.0 as usize];
+      if *distance == HeuristicValue::MAX {
+        *distance = depth;
+        remaining -= 1;
+      }
+    }
+
+    if remaining == 0 || depth as usize >= max_depth {
+      continue;
+    }
+
+    let parent_hash = zobrist_hash(actor_squares.as_bytes());
+    let move_destinations =
+      board.get_all_actor_move_destinations(actor_squares);
+    for (actor_index, move_destinations) in
+      move_destinations.into_iter().enumerate()
+    {
+      let actor_square = actor_squares.0[actor_index];
+      for move_destination in move_destinations {
+        if move_destination == actor_square {
+          continue;
+        }
+        let visited_key =
+          roll_zobrist_hash(parent_hash, actor_square.0, move_destination.0);
+        if !visited.insert(visited_key) {
+          continue;
+        }
+        let mut new_actor_squares = actor_squares;
+        new_actor_squares.0[actor_index] = move_destination;
+        queue.push_back((new_actor_squares, depth + 1));
+      }
+    }
+  }
+
+  distances
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::mechanics::B64EncodedCompressedPosition;
+  use crate::mechanics::CompressedPosition;
+  use crate::mechanics::Square;
+  use crate::solvers::astar::solve;
+
+  #[test]
+  fn test_starting_squares_are_zero() {
+    let actor_squares =
+      ActorSquares([Square(0), Square(1), Square(2), Square(3)]);
+    let distances = solve_all_goals(&MoveBoard::EMPTY, actor_squares, 1);
+    for square in actor_squares.0 {
+      assert_eq!(distances[square.0 as usize], 0);
+    }
+    assert_eq!(distances[240], 1);
+    assert_eq!(distances[17], HeuristicValue::MAX);
+  }
+
+  #[test]
+  fn test_matches_single_goal_solves() {
+    let &(_, position_b64, _) =
+      inertia_fixtures::get_sample_position("random_classic_gen_15").unwrap();
+    let position = Position::try_from(
+      CompressedPosition::try_from(B64EncodedCompressedPosition(
+        position_b64.to_owned(),
+      ))
+      .unwrap(),
+    )
+    .unwrap();
+    let board = MoveBoard::from(&position.walled_board);
+    let distances = solve_all_goals(&board, position.actor_squares, 3);
+    for goal in 0..=255 {
+      let distance = distances[goal as usize];
+      let solution = solve(&board, Square(goal), position.actor_squares, 3);
+      match solution {
+        Some(solution) => assert_eq!(solution.0.len(), distance as usize),
+        None => assert_eq!(distance, HeuristicValue::MAX),
+      }
+    }
+  }
+}
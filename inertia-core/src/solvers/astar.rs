@@ -12,9 +12,28 @@ use crate::solvers::zobrist_hash;
 use crate::solvers::BucketingPriorityQueue;
 use crate::solvers::GroupMinMovesBoard;
 use crate::solvers::Heuristic;
+use crate::solvers::HeuristicValue;
+use crate::solvers::MinMovesBoard;
 use crate::solvers::NoopHasherBuilder;
 use crate::solvers::Solution;
 use crate::solvers::SolutionStep;
+use serde::Deserialize;
+use serde::Serialize;
+use thiserror::Error;
+
+#[cfg(feature = "web")]
+use {tsify::Tsify, wasm_bindgen::prelude::wasm_bindgen};
+
+#[derive(Error, Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "web", derive(Tsify), tsify(into_wasm_abi, from_wasm_abi))]
+pub enum NoSolutionReason {
+  #[error("No solution exists within the maximum search depth")]
+  ExceedsMaxDepth,
+  #[error("No solution exists")]
+  Unsolvable,
+  #[error("No solution was found within the search limit")]
+  ExceedsNodeLimit,
+}
 
 struct VisitedData {
   parent: ActorSquares,
@@ -30,13 +49,20 @@ pub fn solve_position<P: Borrow<Position>>(
   position: P,
   max_depth: usize,
 ) -> Option<Solution> {
+  try_solve_position(position, max_depth).ok()
+}
+
+pub fn try_solve_position<P: Borrow<Position>>(
+  position: P,
+  max_depth: usize,
+) -> Result<Solution, NoSolutionReason> {
   let Position {
     walled_board,
     actor_squares,
     goal,
   } = position.borrow();
   let board = MoveBoard::from(walled_board);
-  solve(&board, *goal, *actor_squares, max_depth)
+  try_solve(&board, *goal, *actor_squares, max_depth)
 }
 
 pub fn solve(
@@ -45,6 +71,106 @@ pub fn solve(
   actor_squares: ActorSquares,
   max_depth: usize,
 ) -> Option<Solution> {
+  try_solve(board, goal, actor_squares, max_depth).ok()
+}
+
+// If no actor could reach the goal even if it were able to stop on any square,
+// there is no need to search at all.
+pub fn is_statically_unsolvable(
+  board: &MoveBoard,
+  goal: Square,
+  actor_squares: ActorSquares,
+) -> bool {
+  MinMovesBoard::from_move_board(board, goal).get_heuristic(actor_squares)
+    == HeuristicValue::MAX
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SolveStats {
+  pub nodes_expanded: usize,
+  pub nodes_generated: usize,
+}
+
+impl SolveStats {
+  // Average number of new arrangements queued per expanded arrangement
+  pub fn branching_factor(&self) -> f64 {
+    if self.nodes_expanded == 0 {
+      return 0.0;
+    }
+    self.nodes_generated as f64 / self.nodes_expanded as f64
+  }
+}
+
+pub fn try_solve(
+  board: &MoveBoard,
+  goal: Square,
+  actor_squares: ActorSquares,
+  max_depth: usize,
+) -> Result<Solution, NoSolutionReason> {
+  try_solve_with_stats(
+    board,
+    goal,
+    actor_squares,
+    max_depth,
+    &mut SolveStats::default(),
+  )
+}
+
+pub fn try_solve_with_stats(
+  board: &MoveBoard,
+  goal: Square,
+  actor_squares: ActorSquares,
+  max_depth: usize,
+  stats: &mut SolveStats,
+) -> Result<Solution, NoSolutionReason> {
+  try_solve_with_limits(
+    board,
+    goal,
+    actor_squares,
+    max_depth,
+    usize::MAX,
+    stats,
+  )
+}
+
+// Like solving normally, but gives up once stats count max_nodes expanded
+// arrangements, for positions that can't be trusted to keep the search small.
+// Stats carry over between calls, so reusing them makes max_nodes a budget for
+// several solves together.
+pub fn try_solve_position_with_limits<P: Borrow<Position>>(
+  position: P,
+  max_depth: usize,
+  max_nodes: usize,
+  stats: &mut SolveStats,
+) -> Result<Solution, NoSolutionReason> {
+  let Position {
+    walled_board,
+    actor_squares,
+    goal,
+  } = position.borrow();
+  let board = MoveBoard::from(walled_board);
+  try_solve_with_limits(
+    &board,
+    *goal,
+    *actor_squares,
+    max_depth,
+    max_nodes,
+    stats,
+  )
+}
+
+pub fn try_solve_with_limits(
+  board: &MoveBoard,
+  goal: Square,
+  actor_squares: ActorSquares,
+  max_depth: usize,
+  max_nodes: usize,
+  stats: &mut SolveStats,
+) -> Result<Solution, NoSolutionReason> {
+  if is_statically_unsolvable(board, goal, actor_squares) {
+    return Err(NoSolutionReason::Unsolvable);
+  }
+
   let heuristic_board = GroupMinMovesBoard::from_move_board(board, goal);
   let mut queue = BucketingPriorityQueue::with_capacities(256, 1024);
   let mut visited: HashMap<u64, VisitedData, NoopHasherBuilder> =
@@ -65,7 +191,7 @@ pub fn solve(
     } = queue_data;
 
     if depth as usize > max_depth {
-      return None;
+      return Err(NoSolutionReason::ExceedsMaxDepth);
     }
 
     if actor_squares.0.contains(&goal) {
@@ -96,9 +222,13 @@ pub fn solve(
         current_actor_squares = parent;
       }
       solution_steps.reverse();
-      return Some(Solution(solution_steps));
+      return Ok(Solution(solution_steps));
     }
 
+    if stats.nodes_expanded >= max_nodes {
+      return Err(NoSolutionReason::ExceedsNodeLimit);
+    }
+    stats.nodes_expanded += 1;
     let depth_after_move = depth + 1;
     let parent_hash = zobrist_hash(actor_squares.as_bytes());
     let move_destinations =
@@ -140,6 +270,7 @@ pub fn solve(
           continue;
         }
 
+        stats.nodes_generated += 1;
         queue.push(
           QueueData {
             actor_squares: new_actor_squares,
@@ -152,12 +283,14 @@ pub fn solve(
     }
   }
 
-  None
+  // Every arrangement reachable from the start has been explored
+  Err(NoSolutionReason::Unsolvable)
 }
 
 #[cfg(test)]
 mod test {
   use super::*;
+  use crate::mechanics::WalledBoard;
 
   #[test]
   fn test_already_solved() {
@@ -248,4 +381,75 @@ mod test {
       ]))
     );
   }
+
+  #[test]
+  fn test_empty_exceeds_max_depth() {
+    let solution = try_solve(
+      &MoveBoard::EMPTY,
+      Square(0),
+      ActorSquares([Square(17), Square(18), Square(19), Square(20)]),
+      1,
+    );
+    assert_eq!(solution, Err(NoSolutionReason::ExceedsMaxDepth));
+  }
+
+  #[test]
+  fn test_walled_off_goal_statically_unsolvable() {
+    let mut walled_board = WalledBoard::EMPTY;
+    walled_board.set_wall_down(Square(0), true);
+    walled_board.set_wall_right(Square(0), true);
+    let board = MoveBoard::from(&walled_board);
+    let actor_squares =
+      ActorSquares([Square(17), Square(18), Square(19), Square(20)]);
+    assert!(is_statically_unsolvable(&board, Square(0), actor_squares));
+    assert_eq!(
+      try_solve(&board, Square(0), actor_squares, 255),
+      Err(NoSolutionReason::Unsolvable)
+    );
+  }
+
+  #[test]
+  fn test_node_limit() {
+    let actor_squares =
+      ActorSquares([Square(1), Square(2), Square(3), Square(4)]);
+    let solve_with_limit = |max_nodes| {
+      try_solve_with_limits(
+        &MoveBoard::EMPTY,
+        Square(0),
+        actor_squares,
+        10,
+        max_nodes,
+        &mut SolveStats::default(),
+      )
+    };
+    assert_eq!(solve_with_limit(0), Err(NoSolutionReason::ExceedsNodeLimit));
+    assert!(solve_with_limit(1).is_ok());
+  }
+
+  #[test]
+  fn test_exhausted_search_unsolvable() {
+    // One actor is confined to a three square corridor and the others are each
+    // boxed into a corner. The goal is the middle of the corridor, which is
+    // reachable if an actor could stop anywhere, but nothing can ever come to
+    // rest there.
+    let mut walled_board = WalledBoard::EMPTY;
+    walled_board.set_wall_down(Square(0), true);
+    walled_board.set_wall_down(Square(1), true);
+    walled_board.set_wall_down(Square(2), true);
+    walled_board.set_wall_right(Square(2), true);
+    walled_board.set_wall_left(Square(15), true);
+    walled_board.set_wall_down(Square(15), true);
+    walled_board.set_wall_up(Square(240), true);
+    walled_board.set_wall_right(Square(240), true);
+    walled_board.set_wall_up(Square(255), true);
+    walled_board.set_wall_left(Square(255), true);
+    let board = MoveBoard::from(&walled_board);
+    let actor_squares =
+      ActorSquares([Square(0), Square(15), Square(240), Square(255)]);
+    assert!(!is_statically_unsolvable(&board, Square(1), actor_squares));
+    assert_eq!(
+      try_solve(&board, Square(1), actor_squares, 255),
+      Err(NoSolutionReason::Unsolvable)
+    );
+  }
 }
@@ -7,6 +7,7 @@ use rand::prelude::Distribution;
 use rand::Rng;
 use serde::Deserialize;
 use serde::Serialize;
+use thiserror::Error;
 
 #[cfg(feature = "web")]
 use {tsify::Tsify, wasm_bindgen::prelude::wasm_bindgen};
@@ -47,6 +48,52 @@ impl Difficulty {
   }
 }
 
+#[derive(Error, Debug)]
+#[error("Minimum difficulty {min:?} is above maximum difficulty {max:?}")]
+pub struct InvertedDifficultyRangeError {
+  pub min: Difficulty,
+  pub max: Difficulty,
+}
+
+// An inclusive range of difficulties, checked on creation so that min is never
+// above max
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DifficultyRange {
+  min: Difficulty,
+  max: Difficulty,
+}
+
+impl DifficultyRange {
+  pub fn new(
+    min: Difficulty,
+    max: Difficulty,
+  ) -> Result<Self, InvertedDifficultyRangeError> {
+    if min > max {
+      return Err(InvertedDifficultyRangeError { min, max });
+    }
+    Ok(Self { min, max })
+  }
+
+  pub fn min(&self) -> Difficulty {
+    self.min
+  }
+
+  pub fn max(&self) -> Difficulty {
+    self.max
+  }
+
+  pub fn contains(&self, difficulty: Difficulty) -> bool {
+    self.min <= difficulty && difficulty <= self.max
+  }
+
+  pub fn iter(&self) -> impl Iterator<Item = Difficulty> {
+    (u8::from(self.min)..=u8::from(self.max)).map(|difficulty| {
+      Difficulty::try_from(difficulty)
+        .expect("known range of difficulty values")
+    })
+  }
+}
+
 impl Distribution<Difficulty> for Standard {
   fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Difficulty {
     Difficulty::try_from(rng.gen_range(0..=4))
@@ -81,3 +128,29 @@ pub fn get_solution_internal_difficulty(solution: &Solution) -> usize {
   };
   result
 }
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn difficulty_range_is_inclusive() {
+    let range =
+      DifficultyRange::new(Difficulty::Hard, Difficulty::Hardest).unwrap();
+    assert_eq!(
+      range.iter().collect::<Vec<_>>(),
+      vec![Difficulty::Hard, Difficulty::Hardest]
+    );
+    assert!(range.contains(Difficulty::Hardest));
+    assert!(!range.contains(Difficulty::Medium));
+
+    let single =
+      DifficultyRange::new(Difficulty::Easy, Difficulty::Easy).unwrap();
+    assert_eq!(single.iter().collect::<Vec<_>>(), vec![Difficulty::Easy]);
+  }
+
+  #[test]
+  fn rejects_inverted_difficulty_range() {
+    assert!(DifficultyRange::new(Difficulty::Hard, Difficulty::Easy).is_err());
+  }
+}
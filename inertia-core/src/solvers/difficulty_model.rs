@@ -0,0 +1,349 @@
+use std::borrow::Borrow;
+
+use itertools::Itertools;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::mechanics::MoveBoard;
+use crate::mechanics::Position;
+use crate::solvers::astar::try_solve_with_stats;
+use crate::solvers::count_optimal_solutions;
+use crate::solvers::Difficulty;
+use crate::solvers::GroupMinMovesBoard;
+use crate::solvers::Heuristic;
+use crate::solvers::NoSolutionReason;
+use crate::solvers::Solution;
+use crate::solvers::SolutionStep;
+use crate::solvers::SolveStats;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DifficultyFeatures {
+  pub solution_length: usize,
+  pub unique_actors: usize,
+  pub focus_switches: usize,
+  pub optimal_solution_count: u64,
+  pub branching_factor: f64,
+  pub nodes_expanded: usize,
+  // Moves in the optimal solution that come to rest against another actor
+  // rather than a wall
+  pub actor_ricochets: usize,
+  // How far the solver's initial heuristic falls short of the true distance
+  pub heuristic_undershoot: usize,
+}
+
+impl DifficultyFeatures {
+  pub fn from_position<P: Borrow<Position>>(
+    position: P,
+    max_depth: usize,
+  ) -> Result<(Self, Solution), NoSolutionReason> {
+    let position = position.borrow();
+    let Position {
+      walled_board,
+      actor_squares,
+      goal,
+    } = position;
+    let board = MoveBoard::from(walled_board);
+
+    let mut stats = SolveStats::default();
+    let solution = try_solve_with_stats(
+      &board,
+      *goal,
+      *actor_squares,
+      max_depth,
+      &mut stats,
+    )?;
+
+    let steps = &solution.0;
+    let solution_length = steps.len();
+    let heuristic = GroupMinMovesBoard::from_move_board(&board, *goal)
+      .get_heuristic(*actor_squares) as usize;
+
+    let features = Self {
+      solution_length,
+      unique_actors: steps.iter().map(|step| step.actor).unique().count(),
+      focus_switches: steps.iter().map(|step| step.actor).dedup().count(),
+      optimal_solution_count: count_optimal_solutions(
+        &board,
+        *goal,
+        *actor_squares,
+        solution_length,
+      ),
+      branching_factor: stats.branching_factor(),
+      nodes_expanded: stats.nodes_expanded,
+      actor_ricochets: count_actor_ricochets(&board, position, &solution),
+      heuristic_undershoot: solution_length.saturating_sub(heuristic),
+    };
+    Ok((features, solution))
+  }
+}
+
+fn count_actor_ricochets(
+  board: &MoveBoard,
+  position: &Position,
+  solution: &Solution,
+) -> usize {
+  let mut actor_squares = position.actor_squares;
+  let mut ricochets = 0;
+  for &SolutionStep { actor, direction } in &solution.0 {
+    let actor_square = actor_squares.0[actor as usize];
+    let destination =
+      board.get_move_destination(actor_square, actor_squares, direction);
+    if destination
+      != board.get_unimpeded_move_destination(actor_square, direction)
+    {
+      ricochets += 1;
+    }
+    actor_squares.0[actor as usize] = destination;
+  }
+  ricochets
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DifficultyWeights {
+  pub solution_length: f64,
+  pub unique_actors: f64,
+  pub focus_switches: f64,
+  pub log_optimal_solution_count: f64,
+  pub branching_factor: f64,
+  pub log_nodes_expanded: f64,
+  pub actor_ricochets: f64,
+  pub heuristic_undershoot: f64,
+}
+
+// A linear score over the features, bucketed into a Difficulty by thresholds.
+// Threshold i is the lowest score that is rated above Difficulty i.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DifficultyModel {
+  pub weights: DifficultyWeights,
+  pub thresholds: [f64; 4],
+}
+
+impl Default for DifficultyModel {
+  fn default() -> Self {
+    Self {
+      weights: DifficultyWeights {
+        solution_length: 1.0,
+        unique_actors: 0.5,
+        focus_switches: 0.25,
+        log_optimal_solution_count: -0.5,
+        branching_factor: 0.0,
+        log_nodes_expanded: 0.25,
+        actor_ricochets: 0.5,
+        heuristic_undershoot: 0.5,
+      },
+      thresholds: [5.0, 8.5, 13.0, 17.5],
+    }
+  }
+}
+
+impl DifficultyModel {
+  pub fn score(&self, features: &DifficultyFeatures) -> f64 {
+    let DifficultyWeights {
+      solution_length,
+      unique_actors,
+      focus_switches,
+      log_optimal_solution_count,
+      branching_factor,
+      log_nodes_expanded,
+      actor_ricochets,
+      heuristic_undershoot,
+    } = self.weights;
+    solution_length * features.solution_length as f64
+      + unique_actors * features.unique_actors as f64
+      + focus_switches * features.focus_switches as f64
+      + log_optimal_solution_count
+        * (features.optimal_solution_count.max(1) as f64).ln()
+      + branching_factor * features.branching_factor
+      + log_nodes_expanded * (features.nodes_expanded.max(1) as f64).ln()
+      + actor_ricochets * features.actor_ricochets as f64
+      + heuristic_undershoot * features.heuristic_undershoot as f64
+  }
+
+  pub fn difficulty_for_score(&self, score: f64) -> Difficulty {
+    let level = self
+      .thresholds
+      .iter()
+      .take_while(|&&threshold| score >= threshold)
+      .count();
+    Difficulty::try_from(level as u8).expect("at most 4 thresholds")
+  }
+
+  pub fn estimate(&self, features: &DifficultyFeatures) -> Difficulty {
+    self.difficulty_for_score(self.score(features))
+  }
+
+  // Picks the thresholds that rate the most labelled samples correctly for the
+  // current weights. The weights themselves are left alone.
+  pub fn fit_thresholds(
+    &mut self,
+    samples: &[(DifficultyFeatures, Difficulty)],
+  ) {
+    let scored_samples = samples
+      .iter()
+      .map(|(features, difficulty)| (self.score(features), *difficulty))
+      .collect::<Vec<_>>();
+    self.thresholds = fit_thresholds(&scored_samples);
+  }
+
+  pub fn accuracy(&self, samples: &[(DifficultyFeatures, Difficulty)]) -> f64 {
+    if samples.is_empty() {
+      return 0.0;
+    }
+    let correct = samples
+      .iter()
+      .filter(|(features, difficulty)| self.estimate(features) == *difficulty)
+      .count();
+    correct as f64 / samples.len() as f64
+  }
+}
+
+const DIFFICULTY_LEVELS: usize = 5;
+
+// Samples are sorted by score and grouped by equal score, since equal scores
+// must be rated the same. Ratings must then be non-decreasing from group to
+// group, so a dynamic program over (group, rating) finds the rating assignment
+// that matches the most labels. Thresholds are placed halfway between the
+// groups where the rating steps up.
+pub fn fit_thresholds(scored_samples: &[(f64, Difficulty)]) -> [f64; 4] {
+  let mut sorted = scored_samples.to_vec();
+  sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+  let groups = sorted
+    .into_iter()
+    .chunk_by(|(score, _)| *score)
+    .into_iter()
+    .map(|(score, group)| {
+      let mut label_counts = [0usize; DIFFICULTY_LEVELS];
+      for (_, difficulty) in group {
+        label_counts[u8::from(difficulty) as usize] += 1;
+      }
+      (score, label_counts)
+    })
+    .collect::<Vec<_>>();
+
+  if groups.is_empty() {
+    return DifficultyModel::default().thresholds;
+  }
+
+  // best[i][level] is the most matches for groups 0..=i with group i rated
+  // at most level
+  let mut best = vec![[0usize; DIFFICULTY_LEVELS]; groups.len()];
+  let mut choice = vec![[0usize; DIFFICULTY_LEVELS]; groups.len()];
+  for (i, (_, label_counts)) in groups.iter().enumerate() {
+    for level in 0..DIFFICULTY_LEVELS {
+      let previous = if i == 0 { 0 } else { best[i - 1][level] };
+      let here = previous + label_counts[level];
+      let (value, chosen) = if level > 0 && best[i][level - 1] >= here {
+        (best[i][level - 1], choice[i][level - 1])
+      } else {
+        (here, level)
+      };
+      best[i][level] = value;
+      choice[i][level] = chosen;
+    }
+  }
+
+  let mut ratings = vec![0usize; groups.len()];
+  let mut level = DIFFICULTY_LEVELS - 1;
+  for i in (0..groups.len()).rev() {
+    ratings[i] = choice[i][level];
+    level = ratings[i];
+  }
+
+  // Levels that no sample is rated at get thresholds outside the range of
+  // scores. These stay finite so that models round trip through JSON.
+  let mut thresholds = [f64::MAX; 4];
+  for i in 0..groups.len() {
+    let lowest_level = if i == 0 { 0 } else { ratings[i - 1] };
+    let threshold = if i == 0 {
+      f64::MIN
+    } else {
+      (groups[i - 1].0 + groups[i].0) / 2.0
+    };
+    thresholds[lowest_level..ratings[i]].fill(threshold);
+  }
+  thresholds
+}
+
+pub fn get_position_difficulty<P: Borrow<Position>>(
+  position: P,
+  max_depth: usize,
+) -> Result<Difficulty, NoSolutionReason> {
+  let (features, _) = DifficultyFeatures::from_position(position, max_depth)?;
+  Ok(DifficultyModel::default().estimate(&features))
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::mechanics::ActorSquares;
+  use crate::mechanics::Square;
+  use crate::mechanics::WalledBoard;
+
+  #[test]
+  fn test_fit_separable() {
+    let samples = [
+      (1.0, Difficulty::Easiest),
+      (2.0, Difficulty::Easiest),
+      (3.0, Difficulty::Easy),
+      (5.0, Difficulty::Medium),
+      (6.0, Difficulty::Medium),
+      (9.0, Difficulty::Hard),
+      (12.0, Difficulty::Hardest),
+    ];
+    assert_eq!(fit_thresholds(&samples), [2.5, 4.0, 7.5, 10.5]);
+  }
+
+  #[test]
+  fn test_fit_overlapping() {
+    let samples = [
+      (1.0, Difficulty::Easiest),
+      (2.0, Difficulty::Easy),
+      (3.0, Difficulty::Easiest),
+      (4.0, Difficulty::Easy),
+      (5.0, Difficulty::Easy),
+    ];
+    let thresholds = fit_thresholds(&samples);
+    let model = DifficultyModel {
+      thresholds,
+      ..Default::default()
+    };
+    let correct = samples
+      .iter()
+      .filter(|(score, difficulty)| {
+        model.difficulty_for_score(*score) == *difficulty
+      })
+      .count();
+    assert_eq!(correct, 4);
+  }
+
+  #[test]
+  fn test_empty_position_features() {
+    let position = Position {
+      walled_board: WalledBoard::EMPTY,
+      actor_squares: ActorSquares([17, 18, 19, 20].map(Square)),
+      goal: Square(0),
+    };
+    let (features, solution) =
+      DifficultyFeatures::from_position(position, 255).unwrap();
+    assert!(position.is_solution(&solution));
+    assert_eq!(features.solution_length, 2);
+    assert_eq!(features.unique_actors, 1);
+    assert_eq!(features.optimal_solution_count, 5);
+    assert_eq!(features.actor_ricochets, 0);
+    assert!(features.nodes_expanded > 0);
+  }
+
+  #[test]
+  fn test_ricochet_counted() {
+    // Actor 0 moves right and stops against actor 1
+    let position = Position {
+      walled_board: WalledBoard::EMPTY,
+      actor_squares: ActorSquares([0, 3, 254, 255].map(Square)),
+      goal: Square(2),
+    };
+    let (features, _) =
+      DifficultyFeatures::from_position(position, 255).unwrap();
+    assert_eq!(features.solution_length, 1);
+    assert_eq!(features.actor_ricochets, 1);
+  }
+}
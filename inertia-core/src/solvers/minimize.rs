@@ -0,0 +1,220 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::mechanics::Direction;
+use crate::mechanics::Position;
+use crate::mechanics::SolvedPosition;
+use crate::mechanics::Square;
+use crate::mechanics::WalledBoard;
+use crate::solvers::astar::solve_position;
+
+// A single wall segment, named by the square on its left or top side. The
+// direction is always Right or Down.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WallSegment {
+  pub square: Square,
+  pub direction: Direction,
+}
+
+impl WallSegment {
+  pub fn all(walled_board: &WalledBoard) -> Vec<Self> {
+    (0..=255)
+      .map(Square)
+      .flat_map(|square| {
+        [
+          (Direction::Right, walled_board.get_wall_right(square, false)),
+          (Direction::Down, walled_board.get_wall_down(square, false)),
+        ]
+        .into_iter()
+        .filter(|&(_, is_wall)| is_wall)
+        .map(move |(direction, _)| Self { square, direction })
+      })
+      .collect()
+  }
+
+  fn set(&self, walled_board: &mut WalledBoard, value: bool) {
+    match self.direction {
+      Direction::Right => walled_board.set_wall_right(self.square, value),
+      Direction::Down => walled_board.set_wall_down(self.square, value),
+      Direction::Up => walled_board.set_wall_up(self.square, value),
+      Direction::Left => walled_board.set_wall_left(self.square, value),
+    }
+  }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MinimizedPosition {
+  pub solved_position: SolvedPosition,
+  // Walls that could not be removed without changing the optimal length
+  pub essential_walls: Vec<WallSegment>,
+  pub removed_walls: Vec<WallSegment>,
+  // Actors moved out of play into the central box
+  pub parked_actors: Vec<usize>,
+}
+
+const CENTRAL_BOX_SQUARES: [Square; 4] =
+  [Square(119), Square(120), Square(135), Square(136)];
+
+// Walls that close off the central box, so that an actor inside it can't
+// interact with the rest of the board
+fn central_box_walls() -> [WallSegment; 8] {
+  [
+    (103, Direction::Down),
+    (104, Direction::Down),
+    (135, Direction::Down),
+    (136, Direction::Down),
+    (118, Direction::Right),
+    (134, Direction::Right),
+    (120, Direction::Right),
+    (136, Direction::Right),
+  ]
+  .map(|(square, direction)| WallSegment {
+    square: Square(square),
+    direction,
+  })
+}
+
+// Whether the position still has an optimal solution of exactly the given
+// length, returning that solution
+fn solve_at_length(
+  position: &Position,
+  solution_length: usize,
+) -> Option<SolvedPosition> {
+  let solution = solve_position(position, solution_length)?;
+  (solution.0.len() == solution_length).then_some(SolvedPosition {
+    position: *position,
+    solution,
+  })
+}
+
+// Greedily simplifies a puzzle while keeping its optimal solution length.
+// Each wall segment is removed in turn and kept out if the position still
+// solves optimally in the same number of moves. With remove_helper_actors,
+// actors are first tried in the central box, where they can't affect play,
+// and the box walls are then kept. The result depends on the order things are
+// tried in, so it is minimal in the sense that no single remaining wall can be
+// removed, not the smallest possible board.
+pub fn minimize_solved_position(
+  solved_position: &SolvedPosition,
+  remove_helper_actors: bool,
+) -> MinimizedPosition {
+  let solution_length = solved_position.solution.0.len();
+  let mut current = solved_position.clone();
+  let mut parked_actors = Vec::new();
+
+  let box_walls = central_box_walls();
+  let is_box_closed = box_walls.iter().all(|wall| {
+    WallSegment::all(&current.position.walled_board).contains(wall)
+  });
+  let can_park = remove_helper_actors
+    && is_box_closed
+    && !CENTRAL_BOX_SQUARES.contains(&current.position.goal);
+
+  if can_park {
+    for actor_index in 0..current.position.actor_squares.0.len() {
+      let Some(&parking_square) = CENTRAL_BOX_SQUARES
+        .iter()
+        .find(|square| !current.position.actor_squares.0.contains(square))
+      else {
+        break;
+      };
+      let mut position = current.position;
+      position.actor_squares.0[actor_index] = parking_square;
+      if let Some(solved) = solve_at_length(&position, solution_length) {
+        current = solved;
+        parked_actors.push(actor_index);
+      }
+    }
+  }
+
+  // A wall that is needed early on can become removable once others are
+  // gone, so keep going until a full pass removes nothing
+  let mut removed_walls = Vec::new();
+  loop {
+    let mut removed_any = false;
+    for wall in WallSegment::all(&current.position.walled_board) {
+      // Parked actors rely on the box to stay out of play
+      if !parked_actors.is_empty() && box_walls.contains(&wall) {
+        continue;
+      }
+      let mut position = current.position;
+      wall.set(&mut position.walled_board, false);
+      if let Some(solved) = solve_at_length(&position, solution_length) {
+        current = solved;
+        removed_walls.push(wall);
+        removed_any = true;
+      }
+    }
+    if !removed_any {
+      break;
+    }
+  }
+  let essential_walls = WallSegment::all(&current.position.walled_board);
+
+  MinimizedPosition {
+    solved_position: current,
+    essential_walls,
+    removed_walls,
+    parked_actors,
+  }
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::board_generators::ClassicBoardGenerator;
+  use crate::mechanics::ActorSquares;
+  use crate::mechanics::PositionGenerator;
+
+  fn solved(position: Position) -> SolvedPosition {
+    let solution = solve_position(position, 45).unwrap();
+    SolvedPosition { position, solution }
+  }
+
+  #[test]
+  fn keeps_only_needed_wall() {
+    // Actor 0 needs the wall to stop on the goal, the other wall is unused
+    let mut walled_board = WalledBoard::EMPTY;
+    walled_board.set_wall_right(Square(5), true);
+    walled_board.set_wall_down(Square(200), true);
+    let position = Position {
+      walled_board,
+      actor_squares: ActorSquares([0, 255, 254, 253].map(Square)),
+      goal: Square(5),
+    };
+    let minimized = minimize_solved_position(&solved(position), false);
+    let essential = WallSegment {
+      square: Square(5),
+      direction: Direction::Right,
+    };
+    assert_eq!(minimized.essential_walls, vec![essential]);
+    assert_eq!(minimized.removed_walls.len(), 1);
+    assert_eq!(minimized.solved_position.solution.0.len(), 1);
+  }
+
+  #[test]
+  fn preserves_optimal_length() {
+    for seed in 0..3 {
+      let position =
+        ClassicBoardGenerator::new().generate_position_from_seed(seed);
+      let original = solved(position);
+      let minimized = minimize_solved_position(&original, true);
+      let SolvedPosition { position, solution } = &minimized.solved_position;
+      assert!(position.is_solution(solution));
+      assert_eq!(solution.0.len(), original.solution.0.len());
+      assert_eq!(
+        WallSegment::all(&position.walled_board),
+        minimized.essential_walls
+      );
+      for wall in &minimized.essential_walls {
+        let mut position = *position;
+        if minimized.parked_actors.is_empty()
+          || !central_box_walls().contains(wall)
+        {
+          wall.set(&mut position.walled_board, false);
+          assert!(solve_at_length(&position, solution.0.len()).is_none());
+        }
+      }
+    }
+  }
+}
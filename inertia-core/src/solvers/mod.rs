@@ -1,6 +1,9 @@
 pub mod astar;
 pub use astar::*;
 
+pub mod all_goals;
+pub use all_goals::*;
+
 mod noop_hasher;
 pub use noop_hasher::*;
 
@@ -25,6 +28,15 @@ pub use queue::*;
 pub mod difficulty;
 pub use difficulty::*;
 
+pub mod difficulty_model;
+pub use difficulty_model::*;
+
+mod optimal_solutions;
+pub use optimal_solutions::*;
+
+pub mod minimize;
+pub use minimize::*;
+
 pub mod heuristic;
 pub use heuristic::*;
 
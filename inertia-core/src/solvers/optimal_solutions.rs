@@ -0,0 +1,352 @@
+use std::collections::HashMap;
+
+use crate::mechanics::ActorSquares;
+use crate::mechanics::Direction;
+use crate::mechanics::MoveBoard;
+use crate::mechanics::SolvedPosition;
+use crate::mechanics::Square;
+use crate::solvers::roll_zobrist_hash;
+use crate::solvers::zobrist_hash;
+use crate::solvers::GroupMinMovesBoard;
+use crate::solvers::Heuristic;
+use crate::solvers::Solution;
+use crate::solvers::SolutionStep;
+
+// Enough to cover most positions with commuting moves, while keeping the
+// pairwise checks cheap
+pub const DEFAULT_MAX_ENUMERATED_SOLUTIONS: usize = 2000;
+
+struct OptimalSolutionCounter<'a> {
+  board: &'a MoveBoard,
+  goal: Square,
+  heuristic_board: GroupMinMovesBoard,
+  memo: HashMap<(u64, u8), u64>,
+}
+
+impl OptimalSolutionCounter<'_> {
+  // The number of move sequences from the given arrangement that finish on the
+  // goal in exactly remaining moves. Results only depend on which squares are
+  // occupied, so arrangements are keyed by their order independent hash.
+  fn count(
+    &mut self,
+    actor_squares: ActorSquares,
+    hash: u64,
+    remaining: u8,
+  ) -> u64 {
+    if actor_squares.0.contains(&self.goal) {
+      return (remaining == 0) as u64;
+    }
+    if self.heuristic_board.get_heuristic(actor_squares) > remaining {
+      return 0;
+    }
+    if let Some(&count) = self.memo.get(&(hash, remaining)) {
+      return count;
+    }
+
+    let mut count = 0u64;
+    let move_destinations =
+      self.board.get_all_actor_move_destinations(actor_squares);
+    for (actor_index, move_destinations) in
+      move_destinations.into_iter().enumerate()
+    {
+      let actor_square = actor_squares.0[actor_index];
+      for move_destination in move_destinations {
+        if move_destination == actor_square {
+          continue;
+        }
+        let mut new_actor_squares = actor_squares;
+        new_actor_squares.0[actor_index] = move_destination;
+        let new_hash =
+          roll_zobrist_hash(hash, actor_square.0, move_destination.0);
+        count = count.saturating_add(self.count(
+          new_actor_squares,
+          new_hash,
+          remaining - 1,
+        ));
+      }
+    }
+
+    self.memo.insert((hash, remaining), count);
+    count
+  }
+
+  // Collects every move sequence that count would count
+  fn enumerate(
+    &mut self,
+    actor_squares: ActorSquares,
+    hash: u64,
+    remaining: u8,
+    steps: &mut Vec<SolutionStep>,
+    solutions: &mut Vec<Solution>,
+  ) {
+    if actor_squares.0.contains(&self.goal) {
+      if remaining == 0 {
+        solutions.push(Solution(steps.clone()));
+      }
+      return;
+    }
+    if remaining == 0 {
+      return;
+    }
+
+    let move_destinations =
+      self.board.get_all_actor_move_destinations(actor_squares);
+    for (actor_index, move_destinations) in
+      move_destinations.into_iter().enumerate()
+    {
+      let actor_square = actor_squares.0[actor_index];
+      for move_destination in move_destinations {
+        if move_destination == actor_square {
+          continue;
+        }
+        let mut new_actor_squares = actor_squares;
+        new_actor_squares.0[actor_index] = move_destination;
+        let new_hash =
+          roll_zobrist_hash(hash, actor_square.0, move_destination.0);
+        if self.count(new_actor_squares, new_hash, remaining - 1) == 0 {
+          continue;
+        }
+        steps.push(SolutionStep {
+          actor: actor_index as u8,
+          direction: move_direction(actor_square, move_destination),
+        });
+        self.enumerate(
+          new_actor_squares,
+          new_hash,
+          remaining - 1,
+          steps,
+          solutions,
+        );
+        steps.pop();
+      }
+    }
+  }
+}
+
+fn move_direction(from: Square, to: Square) -> Direction {
+  match to.0 as i16 - from.0 as i16 {
+    -255..=-16 => Direction::Up,
+    -15..=-1 => Direction::Left,
+    1..=15 => Direction::Right,
+    _ => Direction::Down,
+  }
+}
+
+// Counts the distinct move sequences of the given length that solve the
+// position. Only meaningful when solution_length is the optimal length, since
+// shorter solutions are not counted.
+pub fn count_optimal_solutions(
+  board: &MoveBoard,
+  goal: Square,
+  actor_squares: ActorSquares,
+  solution_length: usize,
+) -> u64 {
+  let Ok(solution_length) = u8::try_from(solution_length) else {
+    return 0;
+  };
+  let mut counter = OptimalSolutionCounter {
+    board,
+    goal,
+    heuristic_board: GroupMinMovesBoard::from_move_board(board, goal),
+    memo: HashMap::new(),
+  };
+  counter.count(
+    actor_squares,
+    zobrist_hash(actor_squares.as_bytes()),
+    solution_length,
+  )
+}
+
+// Whether all optimal solutions are the same up to move commutation, that is
+// each can be turned into any other by swapping neighbouring moves of
+// different actors where both orders lead to the same arrangement. Positions
+// with more than max_solutions optimal solutions are reported as not unique
+// without being checked.
+pub fn has_unique_optimal_solution(
+  board: &MoveBoard,
+  goal: Square,
+  actor_squares: ActorSquares,
+  solution_length: usize,
+  max_solutions: usize,
+) -> bool {
+  let Ok(length) = u8::try_from(solution_length) else {
+    return false;
+  };
+  let mut counter = OptimalSolutionCounter {
+    board,
+    goal,
+    heuristic_board: GroupMinMovesBoard::from_move_board(board, goal),
+    memo: HashMap::new(),
+  };
+  let hash = zobrist_hash(actor_squares.as_bytes());
+  let count = counter.count(actor_squares, hash, length);
+  if count <= 1 {
+    return count == 1;
+  }
+  if count > max_solutions as u64 {
+    return false;
+  }
+
+  let mut solutions = Vec::new();
+  counter.enumerate(
+    actor_squares,
+    hash,
+    length,
+    &mut Vec::new(),
+    &mut solutions,
+  );
+  let indices: HashMap<&[SolutionStep], usize> = solutions
+    .iter()
+    .enumerate()
+    .map(|(index, solution)| (solution.0.as_slice(), index))
+    .collect();
+
+  // Union find over solutions joined by a single commuting swap
+  let mut parents: Vec<usize> = (0..solutions.len()).collect();
+  fn find(parents: &mut [usize], mut index: usize) -> usize {
+    while parents[index] != index {
+      parents[index] = parents[parents[index]];
+      index = parents[index];
+    }
+    index
+  }
+
+  let mut classes = solutions.len();
+  for (index, solution) in solutions.iter().enumerate() {
+    let mut before = actor_squares;
+    for step in 0..solution.0.len() {
+      let after = apply_step(board, before, solution.0[step]);
+      if step + 1 < solution.0.len()
+        && solution.0[step].actor != solution.0[step + 1].actor
+      {
+        let after_both = apply_step(board, after, solution.0[step + 1]);
+        let swapped_after_both = apply_step(
+          board,
+          apply_step(board, before, solution.0[step + 1]),
+          solution.0[step],
+        );
+        let mut swapped = solution.0.clone();
+        swapped.swap(step, step + 1);
+        if after_both == swapped_after_both {
+          if let Some(&other) = indices.get(swapped.as_slice()) {
+            let root = find(&mut parents, index);
+            let other_root = find(&mut parents, other);
+            if root != other_root {
+              parents[root] = other_root;
+              classes -= 1;
+            }
+          }
+        }
+      }
+      before = after;
+    }
+  }
+  classes == 1
+}
+
+pub fn is_solved_position_unique(solved_position: &SolvedPosition) -> bool {
+  let SolvedPosition { position, solution } = solved_position;
+  has_unique_optimal_solution(
+    &MoveBoard::from(&position.walled_board),
+    position.goal,
+    position.actor_squares,
+    solution.0.len(),
+    DEFAULT_MAX_ENUMERATED_SOLUTIONS,
+  )
+}
+
+fn apply_step(
+  board: &MoveBoard,
+  mut actor_squares: ActorSquares,
+  SolutionStep { actor, direction }: SolutionStep,
+) -> ActorSquares {
+  let actor_square = actor_squares.0[actor as usize];
+  actor_squares.0[actor as usize] =
+    board.get_move_destination(actor_square, actor_squares, direction);
+  actor_squares
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+
+  #[test]
+  fn test_already_solved() {
+    let count = count_optimal_solutions(
+      &MoveBoard::EMPTY,
+      Square(0),
+      ActorSquares([Square(0), Square(1), Square(2), Square(3)]),
+      0,
+    );
+    assert_eq!(count, 1);
+  }
+
+  #[test]
+  fn test_empty_unique_in_one() {
+    let count = count_optimal_solutions(
+      &MoveBoard::EMPTY,
+      Square(0),
+      ActorSquares([Square(1), Square(2), Square(3), Square(4)]),
+      1,
+    );
+    assert_eq!(count, 1);
+  }
+
+  #[test]
+  fn test_empty_many_in_two() {
+    // Any actor can go up then left, and actor 0 can also go left then up
+    let count = count_optimal_solutions(
+      &MoveBoard::EMPTY,
+      Square(0),
+      ActorSquares([Square(17), Square(18), Square(19), Square(20)]),
+      2,
+    );
+    assert_eq!(count, 5);
+  }
+
+  #[test]
+  fn test_unique_single_solution() {
+    assert!(has_unique_optimal_solution(
+      &MoveBoard::EMPTY,
+      Square(0),
+      ActorSquares([Square(1), Square(2), Square(3), Square(4)]),
+      1,
+      DEFAULT_MAX_ENUMERATED_SOLUTIONS,
+    ));
+  }
+
+  #[test]
+  fn test_not_unique_with_choice_of_actor() {
+    // Each actor can reach the goal on its own
+    assert!(!has_unique_optimal_solution(
+      &MoveBoard::EMPTY,
+      Square(0),
+      ActorSquares([Square(17), Square(18), Square(19), Square(20)]),
+      2,
+      DEFAULT_MAX_ENUMERATED_SOLUTIONS,
+    ));
+  }
+
+  #[test]
+  fn test_unique_up_to_commutation() {
+    // Actor 1 moves left to block actor 0, which moves left then down. The
+    // two left moves can happen in either order.
+    let actor_squares = ActorSquares([
+      Square::from_row_col(0, 5),
+      Square::from_row_col(6, 14),
+      Square::from_row_col(15, 10),
+      Square::from_row_col(15, 11),
+    ]);
+    let goal = Square::from_row_col(5, 0);
+    let count =
+      count_optimal_solutions(&MoveBoard::EMPTY, goal, actor_squares, 3);
+    let unique = has_unique_optimal_solution(
+      &MoveBoard::EMPTY,
+      goal,
+      actor_squares,
+      3,
+      DEFAULT_MAX_ENUMERATED_SOLUTIONS,
+    );
+    assert_eq!((count, unique), (2, true));
+  }
+}
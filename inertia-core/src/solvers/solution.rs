@@ -19,7 +19,7 @@ pub struct Solution(pub Vec<SolutionStep>);
 #[cfg_attr(feature = "web", derive(Tsify), tsify(into_wasm_abi, from_wasm_abi))]
 pub struct CompressedSolution(pub Vec<u8>);
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[cfg_attr(feature = "web", derive(Tsify), tsify(into_wasm_abi, from_wasm_abi))]
 pub struct B64EncodedCompressedSolution(pub String);
 
@@ -142,7 +142,7 @@ impl From<&CompressedSolution> for B64EncodedCompressedSolution {
   }
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[cfg_attr(feature = "web", derive(Tsify), tsify(into_wasm_abi, from_wasm_abi))]
 pub struct SolutionStep {
   pub actor: u8,
@@ -1,14 +1,19 @@
 use std::collections::HashMap;
 use std::hash::Hash;
+use std::mem;
 
 use serde::Deserialize;
 use serde::Serialize;
+use serde::Serializer;
 use strum::Display;
 use thiserror::Error;
 
+use crate::board_generators::GeneratorSpec;
+use crate::board_generators::GeneratorSummary;
 use crate::mechanics::Position;
-use crate::mechanics::SolvedPositionGenerator;
 use crate::solvers::Solution;
+use crate::state::scoring::ScoringPolicy;
+use crate::state::view::SolutionVisibility;
 
 #[cfg(feature = "web")]
 use {tsify::Tsify, wasm_bindgen::prelude::wasm_bindgen};
@@ -105,6 +110,176 @@ impl PlayerBid {
   }
 }
 
+// Spectators watch a room without taking part. They can't bid, solve or start
+// rounds, and aren't counted when waiting for everyone to be ready.
+#[derive(
+  Serialize, Deserialize, Default, Eq, PartialEq, Copy, Clone, Debug,
+)]
+#[cfg_attr(feature = "web", derive(Tsify), tsify(into_wasm_abi, from_wasm_abi))]
+pub enum PlayerRole {
+  #[default]
+  Player,
+  Spectator,
+}
+
+#[derive(Error, Debug)]
+#[error(
+  "Round timer of {}s is outside the allowed {}s to {}s",
+  .0,
+  RoundTimers::MIN_SECS,
+  RoundTimers::MAX_SECS
+)]
+pub struct RoundTimerError(pub u32);
+
+// How long each part of a round lasts before the server moves it along
+#[derive(Serialize, Deserialize, Eq, PartialEq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "web", derive(Tsify), tsify(into_wasm_abi, from_wasm_abi))]
+pub struct RoundTimers {
+  // Until the first bid is made
+  pub round_start_secs: u32,
+  // From the first bid until bids are final
+  pub round_bidding_secs: u32,
+  // For each solver in turn
+  pub round_solving_secs: u32,
+}
+
+impl RoundTimers {
+  pub const MIN_SECS: u32 = 10;
+  pub const MAX_SECS: u32 = 600;
+
+  pub fn validate(&self) -> Result<(), RoundTimerError> {
+    let timers = [
+      self.round_start_secs,
+      self.round_bidding_secs,
+      self.round_solving_secs,
+    ];
+    match timers
+      .into_iter()
+      .find(|secs| !(Self::MIN_SECS..=Self::MAX_SECS).contains(secs))
+    {
+      Some(secs) => Err(RoundTimerError(secs)),
+      None => Ok(()),
+    }
+  }
+}
+
+impl Default for RoundTimers {
+  fn default() -> Self {
+    Self {
+      round_start_secs: 180,
+      round_bidding_secs: 60,
+      round_solving_secs: 60,
+    }
+  }
+}
+
+#[derive(Error, Debug)]
+pub enum GameLengthError {
+  #[error("A game has to be played to at least one point")]
+  NoPoints,
+  #[error("A game has to last at least one round")]
+  NoRounds,
+  #[error(
+    "Time limit of {} minutes is outside the allowed 1 to {}",
+    .0,
+    GameLength::MAX_MINUTES
+  )]
+  TimeLimitOutOfBounds(u32),
+}
+
+// When a game ends. Games only end between rounds, so a round that is being
+// played when the time limit passes is finished first.
+#[derive(
+  Serialize, Deserialize, Default, Eq, PartialEq, Copy, Clone, Debug,
+)]
+#[cfg_attr(feature = "web", derive(Tsify), tsify(into_wasm_abi, from_wasm_abi))]
+#[serde(tag = "type", content = "content")]
+pub enum GameLength {
+  // Until the host ends the game
+  #[default]
+  Unlimited,
+  FirstToPoints {
+    points: u32,
+  },
+  Rounds {
+    rounds: u32,
+  },
+  // Counted from the start of the first round
+  TimeLimit {
+    minutes: u32,
+  },
+}
+
+impl GameLength {
+  pub const MAX_MINUTES: u32 = 240;
+
+  pub fn validate(&self) -> Result<(), GameLengthError> {
+    match *self {
+      GameLength::FirstToPoints { points: 0 } => Err(GameLengthError::NoPoints),
+      GameLength::Rounds { rounds: 0 } => Err(GameLengthError::NoRounds),
+      GameLength::TimeLimit { minutes }
+        if !(1..=Self::MAX_MINUTES).contains(&minutes) =>
+      {
+        Err(GameLengthError::TimeLimitOutOfBounds(minutes))
+      }
+      _ => Ok(()),
+    }
+  }
+}
+
+// When a round entered each of its phases, in milliseconds since the unix
+// epoch. Phases a round skipped are left empty.
+#[derive(
+  Serialize, Deserialize, Default, Eq, PartialEq, Copy, Clone, Debug,
+)]
+#[cfg_attr(feature = "web", derive(Tsify), tsify(into_wasm_abi, from_wasm_abi))]
+pub struct RoundTimings {
+  pub started_at: u64,
+  pub bidding_started_at: Option<u64>,
+  pub solving_started_at: Option<u64>,
+  pub finished_at: Option<u64>,
+}
+
+// How a finished round went
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "web", derive(Tsify), tsify(into_wasm_abi))]
+pub struct RoundResult {
+  pub round_number: u32,
+  pub board: Position,
+  pub optimal_solution: Solution,
+  pub player_bids: PlayerBids,
+  pub solver: Option<PlayerId>,
+  pub solution: Option<Solution>,
+  pub score_deltas: HashMap<PlayerId, i32>,
+  pub timings: RoundTimings,
+}
+
+impl RoundResult {
+  // Only the most recent rounds are kept, so long games don't grow the room
+  // state without bound
+  pub const MAX_KEPT: usize = 50;
+
+  fn finish(round_history: &mut [RoundResult], round: u32, now_millis: u64) {
+    if let Some(last) = round_history
+      .last_mut()
+      .filter(|last| last.round_number == round)
+    {
+      last.timings.finished_at.get_or_insert(now_millis);
+    }
+  }
+}
+
+// A player's place at the end of a game. Players with the same score share a
+// rank.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "web", derive(Tsify), tsify(into_wasm_abi, from_wasm_abi))]
+pub struct Standing {
+  pub rank: u32,
+  pub player_id: PlayerId,
+  pub player_name: PlayerName,
+  pub player_score: u32,
+}
+
 #[derive(Serialize, Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "web", derive(Tsify), tsify(into_wasm_abi))]
 pub struct PlayerInfo {
@@ -118,21 +293,155 @@ pub struct PlayerInfo {
   pub player_score: u32,
 }
 
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "web", derive(Tsify), tsify(into_wasm_abi))]
 pub struct RoomMeta {
   pub room_id: RoomId,
-  #[serde(skip)]
-  pub generator: Box<dyn SolvedPositionGenerator>,
+  // Position lists and playlists hold the upcoming boards and their
+  // solutions, so clients are only sent a summary of the spec
+  #[serde(
+    rename = "generator",
+    serialize_with = "serialize_generator_summary"
+  )]
+  #[cfg_attr(feature = "web", tsify(type = "GeneratorSummary"))]
+  pub generator_spec: GeneratorSpec,
+  // How many boards have been drawn since the spec was set, which for a
+  // playlist is how far through it the room is
+  pub boards_drawn: u32,
+  pub timers: RoundTimers,
+  pub scoring: ScoringPolicy,
+  pub game_length: GameLength,
+  pub solution_visibility: SolutionVisibility,
+  // Set once a timed game runs out of time, so that it ends with the round
+  pub time_limit_reached: bool,
+  // When the first round of the game started, in milliseconds since the unix
+  // epoch. Time limits are counted from here.
+  pub game_started_at: Option<u64>,
+  // The most recent rounds finished this game, oldest first
+  pub round_history: Vec<RoundResult>,
+  // Phase timings of the round in progress
+  pub round_timings: RoundTimings,
   pub player_info: HashMap<PlayerId, PlayerInfo>,
+  pub spectator_info: HashMap<PlayerId, PlayerInfo>,
+  // Players the host kicked, with the reconnect keys they had, so that they
+  // can't come straight back
+  #[serde(skip)]
+  pub kicked_players: HashMap<PlayerId, PlayerReconnectKey>,
+  pub host: Option<PlayerId>,
   pub round_number: u32,
 }
 
-impl PartialEq for RoomMeta {
-  fn eq(&self, other: &Self) -> bool {
-    self.room_id == other.room_id
-      && self.player_info == other.player_info
-      && self.round_number == other.round_number
+fn serialize_generator_summary<S: Serializer>(
+  generator_spec: &GeneratorSpec,
+  serializer: S,
+) -> Result<S::Ok, S::Error> {
+  GeneratorSummary::from(generator_spec).serialize(serializer)
+}
+
+impl RoomMeta {
+  pub fn is_player(&self, player_id: PlayerId) -> bool {
+    self.player_info.contains_key(&player_id)
+  }
+
+  pub fn get_role(&self, player_id: PlayerId) -> Option<PlayerRole> {
+    if self.player_info.contains_key(&player_id) {
+      Some(PlayerRole::Player)
+    } else if self.spectator_info.contains_key(&player_id) {
+      Some(PlayerRole::Spectator)
+    } else {
+      None
+    }
+  }
+
+  pub fn record_round(&mut self, round: RoundResult) {
+    if self.round_history.len() >= RoundResult::MAX_KEPT {
+      self.round_history.remove(0);
+    }
+    self.round_history.push(round);
+  }
+
+  pub fn is_host(&self, player_id: PlayerId) -> bool {
+    self.host == Some(player_id)
+  }
+
+  pub fn check_host(&self, player_id: PlayerId) -> Result<(), NotHostError> {
+    if self.is_host(player_id) {
+      Ok(())
+    } else {
+      Err(NotHostError(player_id))
+    }
+  }
+
+  // The host has to be a connected player. Once they aren't, hosting passes to
+  // the connected player with the lowest id, or to nobody until a player
+  // connects.
+  pub fn update_host(&mut self) {
+    let host_present = self
+      .host
+      .and_then(|host| self.player_info.get(&host))
+      .is_some_and(|info| info.player_connected);
+    if !host_present {
+      self.host = self
+        .player_info
+        .values()
+        .filter(|info| info.player_connected)
+        .map(|info| info.player_id)
+        .min_by_key(|player_id| player_id.0);
+    }
+  }
+
+  // Whether the game has run its length, checked whenever a round finishes
+  pub fn is_game_finished(&self) -> bool {
+    match self.game_length {
+      GameLength::Unlimited => false,
+      GameLength::FirstToPoints { points } => self
+        .player_info
+        .values()
+        .any(|info| info.player_score >= points),
+      GameLength::Rounds { rounds } => self.round_number >= rounds,
+      GameLength::TimeLimit { .. } => self.time_limit_reached,
+    }
+  }
+
+  pub fn standings(&self) -> Vec<Standing> {
+    let mut players: Vec<&PlayerInfo> = self.player_info.values().collect();
+    players.sort_by(|a, b| {
+      b.player_score
+        .cmp(&a.player_score)
+        .then_with(|| a.player_name.0.cmp(&b.player_name.0))
+    });
+
+    let mut standings: Vec<Standing> = Vec::with_capacity(players.len());
+    for (index, info) in players.into_iter().enumerate() {
+      let rank = match standings.last() {
+        Some(previous) if previous.player_score == info.player_score => {
+          previous.rank
+        }
+        _ => index as u32 + 1,
+      };
+      standings.push(Standing {
+        rank,
+        player_id: info.player_id,
+        player_name: info.player_name.clone(),
+        player_score: info.player_score,
+      });
+    }
+    standings
+  }
+
+  // Everyone in the room, whether playing or spectating
+  pub fn everyone(&self) -> impl Iterator<Item = &PlayerInfo> {
+    self
+      .player_info
+      .values()
+      .chain(self.spectator_info.values())
+  }
+
+  pub fn everyone_mut(&mut self) -> impl Iterator<Item = &mut PlayerInfo> {
+    self
+      .player_info
+      .values_mut()
+      .chain(self.spectator_info.values_mut())
   }
 }
 
@@ -146,6 +455,8 @@ pub struct RoundSummary {
   pub last_round_solution: Option<Solution>,
   pub last_solver: Option<PlayerId>,
   pub last_round_optimal_solution: Option<Solution>,
+  // How much each player's score changed in the last round
+  pub last_round_score_deltas: HashMap<PlayerId, i32>,
 }
 
 #[derive(Serialize, Debug, Clone, PartialEq, Eq)]
@@ -179,6 +490,57 @@ pub struct RoundSolving {
   pub optimal_solution: Solution,
 }
 
+// The game has ended, because it ran its length, the host ended it, or there
+// are no more boards to play. Players can still connect to see the results,
+// and the host can start a new game.
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "web", derive(Tsify), tsify(into_wasm_abi))]
+pub struct GameOver {
+  pub meta: RoomMeta,
+  pub last_round_board: Option<Position>,
+  pub last_round_solution: Option<Solution>,
+  pub last_solver: Option<PlayerId>,
+  pub last_round_optimal_solution: Option<Solution>,
+  pub standings: Vec<Standing>,
+  pub round_history: Vec<RoundResult>,
+}
+
+impl GameOver {
+  // The round history moves out of the meta, so that a new game starts
+  // without it
+  pub fn new(
+    mut meta: RoomMeta,
+    last_round_board: Option<Position>,
+    last_round_solution: Option<Solution>,
+    last_solver: Option<PlayerId>,
+    last_round_optimal_solution: Option<Solution>,
+  ) -> Self {
+    let standings = meta.standings();
+    let round_history = mem::take(&mut meta.round_history);
+    GameOver {
+      meta,
+      last_round_board,
+      last_round_solution,
+      last_solver,
+      last_round_optimal_solution,
+      standings,
+      round_history,
+    }
+  }
+}
+
+impl From<RoundSummary> for GameOver {
+  fn from(summary: RoundSummary) -> Self {
+    GameOver::new(
+      summary.meta,
+      summary.last_round_board,
+      summary.last_round_solution,
+      summary.last_solver,
+      summary.last_round_optimal_solution,
+    )
+  }
+}
+
 #[derive(Serialize, Deserialize, Default, Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(feature = "web", derive(Tsify), tsify(into_wasm_abi, from_wasm_abi))]
 pub struct PlayerBids {
@@ -191,6 +553,14 @@ pub struct PlayerBids {
 #[error("Unable to make a bid from the current state")]
 pub struct MakeBidError;
 
+#[derive(Error, Debug)]
+#[error("Player {:?} is spectating and can't take part in rounds", .0)]
+pub struct SpectatorError(pub PlayerId);
+
+#[derive(Error, Debug)]
+#[error("Player {:?} is not the host of the room", .0)]
+pub struct NotHostError(pub PlayerId);
+
 #[derive(Error, Debug)]
 #[error("Unable to ready bid from the current state")]
 pub struct ReadyBidError;
@@ -295,24 +665,41 @@ pub enum RoomState {
   RoundStart(RoundStart),
   RoundBidding(RoundBidding),
   RoundSolving(RoundSolving),
+  GameOver(GameOver),
 }
 
 impl RoomState {
-  pub fn initial<T: SolvedPositionGenerator + 'static>(
+  pub fn initial(
     room_id: RoomId,
-    generator: T,
+    generator_spec: GeneratorSpec,
+    timers: RoundTimers,
+    scoring: ScoringPolicy,
+    game_length: GameLength,
   ) -> Self {
     RoomState::RoundSummary(RoundSummary {
       meta: RoomMeta {
         room_id,
-        generator: Box::new(generator),
+        generator_spec,
+        boards_drawn: 0,
+        timers,
+        scoring,
+        game_length,
+        solution_visibility: SolutionVisibility::default(),
+        time_limit_reached: false,
+        game_started_at: None,
+        round_history: Vec::new(),
+        round_timings: RoundTimings::default(),
         player_info: HashMap::new(),
+        spectator_info: HashMap::new(),
+        kicked_players: HashMap::new(),
+        host: None,
         round_number: 0,
       },
       last_round_board: None,
       last_round_solution: None,
       last_solver: None,
       last_round_optimal_solution: None,
+      last_round_score_deltas: HashMap::new(),
     })
   }
   pub fn get_meta(&self) -> Option<&RoomMeta> {
@@ -323,6 +710,7 @@ impl RoomState {
       RoomState::RoundStart(RoundStart { meta, .. }) => Some(meta),
       RoomState::RoundBidding(RoundBidding { meta, .. }) => Some(meta),
       RoomState::RoundSolving(RoundSolving { meta, .. }) => Some(meta),
+      RoomState::GameOver(GameOver { meta, .. }) => Some(meta),
     }
   }
   pub fn get_meta_mut(&mut self) -> Option<&mut RoomMeta> {
@@ -333,6 +721,49 @@ impl RoomState {
       RoomState::RoundStart(RoundStart { meta, .. }) => Some(meta),
       RoomState::RoundBidding(RoundBidding { meta, .. }) => Some(meta),
       RoomState::RoundSolving(RoundSolving { meta, .. }) => Some(meta),
+      RoomState::GameOver(GameOver { meta, .. }) => Some(meta),
+    }
+  }
+  // Notes the time at which the room entered its current phase
+  pub fn record_phase_start(&mut self, now_millis: u64) {
+    match self {
+      RoomState::None => {}
+      RoomState::Closed => {}
+      RoomState::RoundSummary(RoundSummary { meta, .. }) => {
+        RoundResult::finish(
+          &mut meta.round_history,
+          meta.round_number,
+          now_millis,
+        );
+      }
+      RoomState::RoundStart(RoundStart { meta, .. }) => {
+        if meta.round_number == 1 {
+          meta.game_started_at = Some(now_millis);
+        }
+        meta.round_timings = RoundTimings {
+          started_at: now_millis,
+          ..RoundTimings::default()
+        };
+      }
+      RoomState::RoundBidding(RoundBidding { meta, .. }) => {
+        meta
+          .round_timings
+          .bidding_started_at
+          .get_or_insert(now_millis);
+      }
+      RoomState::RoundSolving(RoundSolving { meta, .. }) => {
+        meta
+          .round_timings
+          .solving_started_at
+          .get_or_insert(now_millis);
+      }
+      RoomState::GameOver(GameOver {
+        meta,
+        round_history,
+        ..
+      }) => {
+        RoundResult::finish(round_history, meta.round_number, now_millis);
+      }
     }
   }
   pub fn get_solver(&self) -> Option<PlayerId> {
@@ -343,6 +774,7 @@ impl RoomState {
       RoomState::RoundStart(_) => None,
       RoomState::RoundBidding(_) => None,
       RoomState::RoundSolving(RoundSolving { solver, .. }) => Some(*solver),
+      RoomState::GameOver(_) => None,
     }
   }
 }
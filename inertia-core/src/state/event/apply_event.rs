@@ -1,3 +1,7 @@
+use std::mem;
+
+use serde::Deserialize;
+use serde::Serialize;
 use strum::Display;
 
 use crate::state::data::RoomState;
@@ -7,28 +11,53 @@ use super::connect::Connect;
 use super::disconnect::hard_disconnect;
 use super::disconnect::soft_disconnect;
 use super::disconnect::Disconnect;
+use super::end_game::end_game;
+use super::end_game::EndGame;
 use super::finalize_bids::round_bidding_finalize_bids;
 use super::finalize_bids::round_start_finalize_bids;
+use super::kick_player::kick_player;
+use super::kick_player::KickPlayer;
 use super::make_bid::round_bidding_make_bid;
 use super::make_bid::round_start_make_bid;
 use super::make_bid::MakeBid;
+use super::new_game::game_over_new_game;
+use super::new_game::NewGame;
 use super::ready_bid::round_bidding_ready_bid;
 use super::ready_bid::round_bidding_unready_bid;
 use super::ready_bid::ReadyBid;
 use super::ready_bid::UnreadyBid;
+use super::rename::rename;
+use super::rename::Rename;
 use super::result::EventError;
 use super::result::EventResult;
+use super::set_role::round_summary_set_role;
+use super::set_role::SetRole;
 use super::start_round::round_summary_start_round;
+use super::start_round::StartRound;
+use super::time_limit::time_limit_reached;
+use super::transfer_host::transfer_host;
+use super::transfer_host::TransferHost;
+use super::update_settings::round_summary_update_settings;
+use super::update_settings::UpdateSettings;
 use super::update_solution::round_solving_update_solution;
 use super::update_solution::UpdateSolution;
 use super::yield_solve::round_solving_yield_solve;
 
-#[derive(Display, Debug, Clone)]
+#[derive(Serialize, Deserialize, Display, Debug, Clone)]
+#[serde(tag = "type", content = "content")]
 pub enum RoomEvent {
   Connect(Connect),
+  Rename(Rename),
+  SetRole(SetRole),
   SoftDisconnect(Disconnect),
   HardDisconnect(Disconnect),
-  StartRound,
+  KickPlayer(KickPlayer),
+  TransferHost(TransferHost),
+  UpdateSettings(UpdateSettings),
+  EndGame(EndGame),
+  NewGame(NewGame),
+  TimeLimitReached,
+  StartRound(StartRound),
   MakeBid(MakeBid),
   ReadyBid(ReadyBid),
   UnreadyBid(UnreadyBid),
@@ -40,8 +69,17 @@ pub enum RoomEvent {
 impl RoomState {
   pub fn apply(self, event: RoomEvent) -> EventResult {
     match (self, event) {
-      (RoomState::RoundSummary(state), RoomEvent::StartRound) => {
-        round_summary_start_round(state)
+      (RoomState::RoundSummary(state), RoomEvent::StartRound(event)) => {
+        round_summary_start_round(state, event)
+      }
+      (RoomState::RoundSummary(state), RoomEvent::UpdateSettings(event)) => {
+        round_summary_update_settings(state, event)
+      }
+      (RoomState::RoundSummary(state), RoomEvent::SetRole(event)) => {
+        round_summary_set_role(state, event)
+      }
+      (RoomState::GameOver(state), RoomEvent::NewGame(event)) => {
+        game_over_new_game(state, event)
       }
       (RoomState::RoundStart(state), RoomEvent::MakeBid(event)) => {
         round_start_make_bid(state, event)
@@ -73,11 +111,28 @@ impl RoomState {
       (state, RoomEvent::SoftDisconnect(event)) => {
         soft_disconnect(state, event)
       }
+      (state, RoomEvent::KickPlayer(event)) => kick_player(state, event),
+      (state, RoomEvent::TransferHost(event)) => transfer_host(state, event),
+      (state, RoomEvent::EndGame(event)) => end_game(state, event),
+      (state, RoomEvent::TimeLimitReached) => time_limit_reached(state),
       (state, RoomEvent::Connect(event)) => connect(state, event),
+      (state, RoomEvent::Rename(event)) => rename(state, event),
       (state, event) => EventResult {
         error: Some(EventError::IncompatibleState(state.to_string(), event)),
         result: state,
       },
     }
   }
+
+  // Applies the event like apply, and notes the time if it moved the room
+  // into a new phase. The clock stays out of the events themselves, so the
+  // caller passes in the current time in milliseconds since the unix epoch.
+  pub fn apply_at(self, event: RoomEvent, now_millis: u64) -> EventResult {
+    let previous_phase = mem::discriminant(&self);
+    let mut result = self.apply(event);
+    if mem::discriminant(&result.result) != previous_phase {
+      result.result.record_phase_start(now_millis);
+    }
+    result
+  }
 }
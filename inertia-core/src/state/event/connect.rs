@@ -1,9 +1,12 @@
+use serde::Deserialize;
+use serde::Serialize;
 use thiserror::Error;
 
 use crate::state::data::PlayerId;
 use crate::state::data::PlayerInfo;
 use crate::state::data::PlayerName;
 use crate::state::data::PlayerReconnectKey;
+use crate::state::data::PlayerRole;
 use crate::state::data::RoomMeta;
 use crate::state::data::RoomState;
 
@@ -12,20 +15,55 @@ use super::result::EventError;
 use super::result::EventResult;
 
 #[derive(Error, Debug)]
-pub enum ConnectError {
+pub enum PlayerNameError {
   #[error("Invalid player name: {:?}", .0)]
   InvalidName(PlayerName),
   #[error("Player name already taken: {:?}", .0)]
   UsernameTaken(PlayerName),
+}
+
+#[derive(Error, Debug)]
+pub enum ConnectError {
+  #[error(transparent)]
+  PlayerNameError(#[from] PlayerNameError),
   #[error("Bad reconnect key {:?} != {:?} for player {:?}", .0, .1, .2)]
   BadReconnectKey(PlayerReconnectKey, PlayerReconnectKey, PlayerName),
+  #[error("Player {:?} was kicked from the room", .0)]
+  Kicked(PlayerId),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Connect {
   pub player_name: PlayerName,
   pub player_id: PlayerId,
   pub player_reconnect_key: PlayerReconnectKey,
+  // Only used the first time a player joins. Returning players keep the role
+  // they had.
+  pub role: PlayerRole,
+}
+
+// A name can be used by a player if it isn't empty and nobody else in the room,
+// playing or spectating, already has it
+pub(super) fn validate_player_name(
+  meta: &RoomMeta,
+  player_id: PlayerId,
+  player_name: &PlayerName,
+) -> Result<(), PlayerNameError> {
+  if player_name.0.is_empty() {
+    return Err(PlayerNameError::InvalidName(player_name.clone()));
+  }
+
+  let required_id = meta
+    .everyone()
+    .find(|info| info.player_name == *player_name)
+    .map(|info| info.player_id);
+  if let Some(required_id) = required_id {
+    if player_id != required_id {
+      return Err(PlayerNameError::UsernameTaken(player_name.clone()));
+    }
+  }
+
+  Ok(())
 }
 
 fn room_meta_connect(
@@ -36,25 +74,23 @@ fn room_meta_connect(
     player_name,
     player_id,
     player_reconnect_key,
+    role,
   } = event;
-  if player_name.0.is_empty() {
-    return Err(ConnectError::InvalidName(player_name));
-  }
-
-  let required_id = meta
-    .player_info
-    .iter()
-    .find(|(_, name)| name.player_name == player_name)
-    .map(|(id, _)| id);
-  if let Some(&required_id) = required_id {
-    if player_id != required_id {
-      return Err(ConnectError::UsernameTaken(player_name));
-    }
+  let is_kicked =
+    meta
+      .kicked_players
+      .iter()
+      .any(|(kicked_id, kicked_reconnect_key)| {
+        *kicked_id == player_id || *kicked_reconnect_key == player_reconnect_key
+      });
+  if is_kicked {
+    return Err(ConnectError::Kicked(player_id));
   }
+  validate_player_name(meta, player_id, &player_name)?;
 
   let required_reconnect_key = meta
-    .player_info
-    .get(&player_id)
+    .everyone()
+    .find(|info| info.player_id == player_id)
     .map(|info| info.player_reconnect_key);
   if let Some(required_reconnect_key) = required_reconnect_key {
     if player_reconnect_key != required_reconnect_key {
@@ -66,22 +102,27 @@ fn room_meta_connect(
     }
   }
 
-  meta
-    .player_info
+  let round_number = meta.round_number;
+  let role_info = match meta.get_role(player_id).unwrap_or(role) {
+    PlayerRole::Player => &mut meta.player_info,
+    PlayerRole::Spectator => &mut meta.spectator_info,
+  };
+  role_info
     .entry(player_id)
     .and_modify(|info| {
       info.player_connected = true;
       info.player_name = player_name.clone();
-      info.player_last_seen = meta.round_number;
+      info.player_last_seen = round_number;
     })
     .or_insert_with(|| PlayerInfo {
       player_id,
       player_name: player_name.clone(),
       player_reconnect_key,
-      player_last_seen: meta.round_number,
+      player_last_seen: round_number,
       player_connected: true,
       player_score: 0,
     });
+  meta.update_host();
 
   Ok(())
 }
@@ -1,3 +1,6 @@
+use serde::Deserialize;
+use serde::Serialize;
+
 use crate::state::data::PlayerId;
 use crate::state::data::RoomMeta;
 use crate::state::data::RoomState;
@@ -6,7 +9,7 @@ use super::apply_event::RoomEvent;
 use super::result::EventError;
 use super::result::EventResult;
 
-#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Disconnect {
   pub player_id: PlayerId,
 }
@@ -14,21 +17,22 @@ pub struct Disconnect {
 fn room_meta_hard_disconnect(meta: &mut RoomMeta, event: Disconnect) {
   let Disconnect { player_id } = event;
   meta.player_info.remove(&player_id);
+  meta.spectator_info.remove(&player_id);
+  meta.update_host();
 }
 
 fn room_meta_soft_disconnect(meta: &mut RoomMeta, event: Disconnect) {
   let Disconnect { player_id } = event;
   meta
-    .player_info
-    .entry(player_id)
-    .and_modify(|e| e.player_connected = false);
+    .everyone_mut()
+    .filter(|info| info.player_id == player_id)
+    .for_each(|info| info.player_connected = false);
+  meta.update_host();
 }
 
+// Spectators keep a room open too
 fn players_still_present(meta: &RoomMeta) -> bool {
-  meta
-    .player_info
-    .iter()
-    .any(|(_, info)| info.player_connected)
+  meta.everyone().any(|info| info.player_connected)
 }
 
 pub fn hard_disconnect(mut state: RoomState, event: Disconnect) -> EventResult {
@@ -0,0 +1,61 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::state::data::GameOver;
+use crate::state::data::PlayerId;
+use crate::state::data::RoomState;
+use crate::state::data::RoundBidding;
+use crate::state::data::RoundSolving;
+use crate::state::data::RoundStart;
+
+use super::apply_event::RoomEvent;
+use super::result::EventError;
+use super::result::EventResult;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EndGame {
+  pub host_id: PlayerId,
+}
+
+// Ends the game early. A round still being played is abandoned, so nobody
+// scores for it and it isn't added to the history, but its board is still
+// shown as the last one played.
+pub fn end_game(state: RoomState, event: EndGame) -> EventResult {
+  let EndGame { host_id } = event;
+  if let Some(Err(error)) =
+    state.get_meta().map(|meta| meta.check_host(host_id))
+  {
+    return EventResult::err(state, error);
+  }
+
+  let game_over = match state {
+    RoomState::RoundSummary(state) => GameOver::from(state),
+    RoomState::RoundStart(RoundStart {
+      meta,
+      board,
+      optimal_solution,
+    })
+    | RoomState::RoundBidding(RoundBidding {
+      meta,
+      board,
+      optimal_solution,
+      ..
+    })
+    | RoomState::RoundSolving(RoundSolving {
+      meta,
+      board,
+      optimal_solution,
+      ..
+    }) => GameOver::new(meta, Some(board), None, None, Some(optimal_solution)),
+    state => {
+      return EventResult {
+        error: Some(EventError::IncompatibleState(
+          state.to_string(),
+          RoomEvent::EndGame(event),
+        )),
+        result: state,
+      }
+    }
+  };
+  EventResult::ok(RoomState::GameOver(game_over))
+}
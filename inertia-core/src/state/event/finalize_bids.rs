@@ -1,10 +1,11 @@
 use crate::solvers::Solution;
+use crate::state::data::PlayerBids;
 use crate::state::data::RoomState;
 use crate::state::data::RoundBidding;
 use crate::state::data::RoundSolving;
 use crate::state::data::RoundStart;
-use crate::state::data::RoundSummary;
 
+use super::finish_round::finish_round;
 use super::result::EventResult;
 
 pub fn round_start_finalize_bids(state: RoundStart) -> EventResult {
@@ -15,13 +16,13 @@ pub fn round_start_finalize_bids(state: RoundStart) -> EventResult {
     ..
   } = state;
 
-  EventResult::ok(RoomState::RoundSummary(RoundSummary {
+  EventResult::ok(finish_round(
     meta,
-    last_round_board: Some(board),
-    last_round_solution: None,
-    last_solver: None,
-    last_round_optimal_solution: Some(optimal_solution),
-  }))
+    board,
+    optimal_solution,
+    &PlayerBids::default(),
+    None,
+  ))
 }
 
 pub fn round_bidding_finalize_bids(state: RoundBidding) -> EventResult {
@@ -45,12 +46,12 @@ pub fn round_bidding_finalize_bids(state: RoundBidding) -> EventResult {
         solution: Solution(Vec::new()),
       }))
     }
-    None => EventResult::ok(RoomState::RoundSummary(RoundSummary {
+    None => EventResult::ok(finish_round(
       meta,
-      last_round_board: Some(board),
-      last_round_solution: None,
-      last_solver: None,
-      last_round_optimal_solution: Some(optimal_solution),
-    })),
+      board,
+      optimal_solution,
+      &player_bids,
+      None,
+    )),
   }
 }
@@ -0,0 +1,58 @@
+use crate::mechanics::Position;
+use crate::solvers::Solution;
+use crate::state::data::GameOver;
+use crate::state::data::PlayerBids;
+use crate::state::data::PlayerId;
+use crate::state::data::RoomMeta;
+use crate::state::data::RoomState;
+use crate::state::data::RoundResult;
+use crate::state::data::RoundSummary;
+use crate::state::scoring::apply_round_score;
+use crate::state::scoring::RoundOutcome;
+
+// Every way a round can end goes through here. The round is scored and added
+// to the history, then the room either waits for the next round or, if the
+// game has run its length, the game is over.
+pub(super) fn finish_round(
+  mut meta: RoomMeta,
+  board: Position,
+  optimal_solution: Solution,
+  player_bids: &PlayerBids,
+  solved: Option<(PlayerId, Solution)>,
+) -> RoomState {
+  let score_deltas = apply_round_score(
+    &mut meta,
+    RoundOutcome {
+      player_bids,
+      optimal_solution: &optimal_solution,
+      solved: solved
+        .as_ref()
+        .map(|(solver, solution)| (*solver, solution)),
+    },
+  );
+  let (solver, solution) = solved.unzip();
+  meta.record_round(RoundResult {
+    round_number: meta.round_number,
+    board,
+    optimal_solution: optimal_solution.clone(),
+    player_bids: player_bids.clone(),
+    solver,
+    solution: solution.clone(),
+    score_deltas: score_deltas.clone(),
+    timings: meta.round_timings,
+  });
+
+  let summary = RoundSummary {
+    meta,
+    last_round_board: Some(board),
+    last_round_solution: solution,
+    last_solver: solver,
+    last_round_optimal_solution: Some(optimal_solution),
+    last_round_score_deltas: score_deltas,
+  };
+  if summary.meta.is_game_finished() {
+    RoomState::GameOver(GameOver::from(summary))
+  } else {
+    RoomState::RoundSummary(summary)
+  }
+}
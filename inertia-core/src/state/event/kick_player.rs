@@ -0,0 +1,86 @@
+use serde::Deserialize;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::state::data::PlayerId;
+use crate::state::data::RoomState;
+
+use super::apply_event::RoomEvent;
+use super::disconnect::hard_disconnect;
+use super::disconnect::Disconnect;
+use super::ready_bid::round_bidding_check_ready;
+use super::result::EventError;
+use super::result::EventResult;
+use super::yield_solve::round_solving_yield_solve;
+
+#[derive(Error, Debug)]
+pub enum KickPlayerError {
+  #[error("Player {:?} is not in the room", .0)]
+  UnknownPlayer(PlayerId),
+  #[error("Host {:?} can't kick themselves", .0)]
+  KickedSelf(PlayerId),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct KickPlayer {
+  pub host_id: PlayerId,
+  pub player_id: PlayerId,
+}
+
+// A kicked player's bid is dropped so they can't be picked to solve, and if
+// they were solving, the next bidder gets their turn. If everyone left has
+// already readied, bidding ends.
+fn drop_from_round(state: RoomState, player_id: PlayerId) -> EventResult {
+  match state {
+    RoomState::RoundBidding(mut state) => {
+      state.player_bids.bids.remove(&player_id);
+      round_bidding_check_ready(state)
+    }
+    RoomState::RoundSolving(state) if state.solver == player_id => {
+      round_solving_yield_solve(state)
+    }
+    RoomState::RoundSolving(mut state) => {
+      state.player_bids.bids.remove(&player_id);
+      EventResult::ok(RoomState::RoundSolving(state))
+    }
+    state => EventResult::ok(state),
+  }
+}
+
+pub fn kick_player(mut state: RoomState, event: KickPlayer) -> EventResult {
+  let KickPlayer { host_id, player_id } = event;
+  let Some(meta) = state.get_meta() else {
+    return EventResult {
+      error: Some(EventError::IncompatibleState(
+        state.to_string(),
+        RoomEvent::KickPlayer(event),
+      )),
+      result: state,
+    };
+  };
+
+  if let Err(error) = meta.check_host(host_id) {
+    return EventResult::err(state, error);
+  }
+  if host_id == player_id {
+    return EventResult::err(state, KickPlayerError::KickedSelf(player_id));
+  }
+  let Some(reconnect_key) = meta
+    .everyone()
+    .find(|info| info.player_id == player_id)
+    .map(|info| info.player_reconnect_key)
+  else {
+    return EventResult::err(state, KickPlayerError::UnknownPlayer(player_id));
+  };
+
+  if let Some(meta) = state.get_meta_mut() {
+    meta.kicked_players.insert(player_id, reconnect_key);
+  }
+
+  let EventResult { result, error } =
+    hard_disconnect(state, Disconnect { player_id });
+  if error.is_some() {
+    return EventResult { result, error };
+  }
+  drop_from_round(result, player_id)
+}
@@ -1,18 +1,29 @@
+use serde::Deserialize;
+use serde::Serialize;
+
 use crate::state::data::PlayerBids;
 use crate::state::data::PlayerId;
 use crate::state::data::RoomState;
 use crate::state::data::RoundBidding;
 use crate::state::data::RoundStart;
+use crate::state::data::SpectatorError;
 
 use super::result::EventResult;
 
-#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct MakeBid {
   pub player_id: PlayerId,
   pub bid_value: u32,
 }
 
 pub fn round_start_make_bid(state: RoundStart, event: MakeBid) -> EventResult {
+  if !state.meta.is_player(event.player_id) {
+    return EventResult::err(
+      RoomState::RoundStart(state),
+      SpectatorError(event.player_id),
+    );
+  }
+
   let RoundStart {
     meta,
     board,
@@ -47,6 +58,13 @@ pub fn round_bidding_make_bid(
   state: RoundBidding,
   event: MakeBid,
 ) -> EventResult {
+  if !state.meta.is_player(event.player_id) {
+    return EventResult::err(
+      RoomState::RoundBidding(state),
+      SpectatorError(event.player_id),
+    );
+  }
+
   let RoundBidding {
     meta,
     board,
@@ -1,10 +1,19 @@
 pub mod apply_event;
 pub mod connect;
 pub mod disconnect;
+pub mod end_game;
 pub mod finalize_bids;
+pub mod finish_round;
+pub mod kick_player;
 pub mod make_bid;
+pub mod new_game;
 pub mod ready_bid;
+pub mod rename;
 pub mod result;
+pub mod set_role;
 pub mod start_round;
+pub mod time_limit;
+pub mod transfer_host;
+pub mod update_settings;
 pub mod update_solution;
 pub mod yield_solve;
@@ -0,0 +1,45 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::state::data::GameOver;
+use crate::state::data::PlayerId;
+use crate::state::data::RoomState;
+use crate::state::data::RoundSummary;
+use crate::state::data::RoundTimings;
+
+use super::result::EventResult;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NewGame {
+  pub host_id: PlayerId,
+}
+
+// Goes back to the lobby with everyone still in the room and their scores
+// reset. The room keeps its settings.
+pub fn game_over_new_game(state: GameOver, event: NewGame) -> EventResult {
+  let NewGame { host_id } = event;
+  if let Err(error) = state.meta.check_host(host_id) {
+    return EventResult::err(RoomState::GameOver(state), error);
+  }
+
+  let GameOver { mut meta, .. } = state;
+  meta.round_number = 0;
+  meta.time_limit_reached = false;
+  meta.game_started_at = None;
+  meta.round_history.clear();
+  meta.round_timings = RoundTimings::default();
+  meta.everyone_mut().for_each(|info| {
+    info.player_score = 0;
+    info.player_last_seen = 0;
+  });
+  EventResult::ok(RoomState::RoundSummary(RoundSummary {
+    meta,
+    last_round_board: None,
+    last_round_solution: None,
+    last_solver: None,
+    last_round_optimal_solution: None,
+    last_round_score_deltas: HashMap::new(),
+  }))
+}
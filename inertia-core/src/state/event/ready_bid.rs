@@ -1,19 +1,23 @@
+use serde::Deserialize;
+use serde::Serialize;
+
 use crate::solvers::Solution;
 use crate::state::data::PlayerBid;
 use crate::state::data::PlayerId;
 use crate::state::data::RoomState;
 use crate::state::data::RoundBidding;
 use crate::state::data::RoundSolving;
-use crate::state::data::RoundSummary;
+use crate::state::data::SpectatorError;
 
+use super::finish_round::finish_round;
 use super::result::EventResult;
 
-#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ReadyBid {
   pub player_id: PlayerId,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct UnreadyBid {
   pub player_id: PlayerId,
 }
@@ -22,6 +26,13 @@ pub fn round_bidding_ready_bid(
   state: RoundBidding,
   event: ReadyBid,
 ) -> EventResult {
+  if !state.meta.is_player(event.player_id) {
+    return EventResult::err(
+      RoomState::RoundBidding(state),
+      SpectatorError(event.player_id),
+    );
+  }
+
   let RoundBidding {
     meta,
     board,
@@ -42,6 +53,24 @@ pub fn round_bidding_ready_bid(
     );
   }
 
+  round_bidding_check_ready(RoundBidding {
+    player_bids,
+    meta,
+    board,
+    optimal_solution,
+  })
+}
+
+// Bidding ends once every player has readied, going to the next solver, or
+// straight to the summary if nobody bid
+pub(super) fn round_bidding_check_ready(state: RoundBidding) -> EventResult {
+  let RoundBidding {
+    meta,
+    board,
+    optimal_solution,
+    player_bids,
+  } = state;
+
   if meta
     .player_info
     .keys()
@@ -65,13 +94,13 @@ pub fn round_bidding_ready_bid(
           solution: Solution(Vec::new()),
         }))
       }
-      None => EventResult::ok(RoomState::RoundSummary(RoundSummary {
+      None => EventResult::ok(finish_round(
         meta,
-        last_round_board: Some(board),
-        last_round_solution: None,
-        last_solver: None,
-        last_round_optimal_solution: Some(optimal_solution),
-      })),
+        board,
+        optimal_solution,
+        &player_bids,
+        None,
+      )),
     };
   }
 
@@ -87,6 +116,13 @@ pub fn round_bidding_unready_bid(
   state: RoundBidding,
   event: UnreadyBid,
 ) -> EventResult {
+  if !state.meta.is_player(event.player_id) {
+    return EventResult::err(
+      RoomState::RoundBidding(state),
+      SpectatorError(event.player_id),
+    );
+  }
+
   let RoundBidding {
     meta,
     board,
@@ -0,0 +1,67 @@
+use serde::Deserialize;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::state::data::PlayerId;
+use crate::state::data::PlayerName;
+use crate::state::data::RoomMeta;
+use crate::state::data::RoomState;
+
+use super::apply_event::RoomEvent;
+use super::connect::validate_player_name;
+use super::connect::PlayerNameError;
+use super::result::EventError;
+use super::result::EventResult;
+
+#[derive(Error, Debug)]
+pub enum RenameError {
+  #[error("Player {:?} is not in the room", .0)]
+  UnknownPlayer(PlayerId),
+  #[error(transparent)]
+  PlayerNameError(#[from] PlayerNameError),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Rename {
+  pub player_id: PlayerId,
+  pub player_name: PlayerName,
+}
+
+fn room_meta_rename(
+  meta: &mut RoomMeta,
+  event: Rename,
+) -> Result<(), RenameError> {
+  let Rename {
+    player_id,
+    player_name,
+  } = event;
+  if meta.get_role(player_id).is_none() {
+    return Err(RenameError::UnknownPlayer(player_id));
+  }
+  validate_player_name(meta, player_id, &player_name)?;
+
+  meta
+    .everyone_mut()
+    .filter(|info| info.player_id == player_id)
+    .for_each(|info| info.player_name = player_name.clone());
+
+  Ok(())
+}
+
+pub fn rename(mut state: RoomState, event: Rename) -> EventResult {
+  if let Some(meta) = state.get_meta_mut() {
+    if let Err(error) = room_meta_rename(meta, event) {
+      EventResult::err(state, error)
+    } else {
+      EventResult::ok(state)
+    }
+  } else {
+    EventResult {
+      error: Some(EventError::IncompatibleState(
+        state.to_string(),
+        RoomEvent::Rename(event),
+      )),
+      result: state,
+    }
+  }
+}
@@ -1,12 +1,20 @@
 use thiserror::Error;
 
+use crate::state::data::GameLengthError;
 use crate::state::data::MakeBidError;
+use crate::state::data::NotHostError;
 use crate::state::data::ReadyBidError;
 use crate::state::data::RoomState;
+use crate::state::data::RoundTimerError;
+use crate::state::data::SpectatorError;
 use crate::state::data::UnreadyBidError;
 
 use super::apply_event::RoomEvent;
 use super::connect::ConnectError;
+use super::kick_player::KickPlayerError;
+use super::rename::RenameError;
+use super::set_role::SetRoleError;
+use super::transfer_host::TransferHostError;
 use super::update_solution::UpdateSolutionError;
 
 #[derive(Error, Debug)]
@@ -16,6 +24,22 @@ pub enum EventError {
   #[error(transparent)]
   ConnectError(#[from] ConnectError),
   #[error(transparent)]
+  RenameError(#[from] RenameError),
+  #[error(transparent)]
+  SetRoleError(#[from] SetRoleError),
+  #[error(transparent)]
+  SpectatorError(#[from] SpectatorError),
+  #[error(transparent)]
+  NotHostError(#[from] NotHostError),
+  #[error(transparent)]
+  KickPlayerError(#[from] KickPlayerError),
+  #[error(transparent)]
+  TransferHostError(#[from] TransferHostError),
+  #[error(transparent)]
+  RoundTimerError(#[from] RoundTimerError),
+  #[error(transparent)]
+  GameLengthError(#[from] GameLengthError),
+  #[error(transparent)]
   UpdateSolutionError(#[from] UpdateSolutionError),
   #[error(transparent)]
   MakeBidError(#[from] MakeBidError),
@@ -0,0 +1,54 @@
+use serde::Deserialize;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::state::data::PlayerId;
+use crate::state::data::PlayerRole;
+use crate::state::data::RoomState;
+use crate::state::data::RoundSummary;
+
+use super::result::EventResult;
+
+#[derive(Error, Debug)]
+pub enum SetRoleError {
+  #[error("Player {:?} is not in the room", .0)]
+  UnknownPlayer(PlayerId),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct SetRole {
+  pub player_id: PlayerId,
+  pub role: PlayerRole,
+}
+
+// Roles can only change between rounds, so that nobody joins or leaves the
+// bidding partway through
+pub fn round_summary_set_role(
+  mut state: RoundSummary,
+  event: SetRole,
+) -> EventResult {
+  let SetRole { player_id, role } = event;
+  let meta = &mut state.meta;
+
+  let Some(current_role) = meta.get_role(player_id) else {
+    return EventResult::err(
+      RoomState::RoundSummary(state),
+      SetRoleError::UnknownPlayer(player_id),
+    );
+  };
+  if current_role == role {
+    return EventResult::ok(RoomState::RoundSummary(state));
+  }
+
+  let (from, to) = match role {
+    PlayerRole::Player => (&mut meta.spectator_info, &mut meta.player_info),
+    PlayerRole::Spectator => (&mut meta.player_info, &mut meta.spectator_info),
+  };
+  if let Some(mut info) = from.remove(&player_id) {
+    info.player_last_seen = meta.round_number;
+    to.insert(player_id, info);
+  }
+  meta.update_host();
+
+  EventResult::ok(RoomState::RoundSummary(state))
+}
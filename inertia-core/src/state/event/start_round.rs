@@ -1,12 +1,44 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::mechanics::SolvedPosition;
+use crate::state::data::GameOver;
+use crate::state::data::PlayerId;
 use crate::state::data::RoomState;
 use crate::state::data::RoundStart;
 use crate::state::data::RoundSummary;
 
 use super::result::EventResult;
 
-pub fn round_summary_start_round(state: RoundSummary) -> EventResult {
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StartRound {
+  pub player_id: PlayerId,
+  // The board for the round, drawn by whoever sends the event so that the
+  // state machine stays deterministic. None when there are no boards left.
+  // Boxed since boards are large compared to other events.
+  pub solved_position: Option<Box<SolvedPosition>>,
+}
+
+// Only the host can start the next round
+pub fn round_summary_start_round(
+  state: RoundSummary,
+  event: StartRound,
+) -> EventResult {
+  let StartRound {
+    player_id,
+    solved_position,
+  } = event;
+  if let Err(error) = state.meta.check_host(player_id) {
+    return EventResult::err(RoomState::RoundSummary(state), error);
+  }
+
+  let Some(solved_position) = solved_position else {
+    return EventResult::ok(RoomState::GameOver(GameOver::from(state)));
+  };
+
   let RoundSummary { mut meta, .. } = state;
   meta.round_number += 1;
+  meta.boards_drawn += 1;
   meta
     .player_info
     .iter_mut()
@@ -14,7 +46,6 @@ pub fn round_summary_start_round(state: RoundSummary) -> EventResult {
     .for_each(|(_, player_info)| {
       player_info.player_last_seen = meta.round_number;
     });
-  let solved_position = meta.generator.generate_solved_position();
   EventResult::ok(RoomState::RoundStart(RoundStart {
     board: solved_position.position,
     optimal_solution: solved_position.solution,
@@ -0,0 +1,28 @@
+use crate::state::data::GameOver;
+use crate::state::data::RoomState;
+
+use super::apply_event::RoomEvent;
+use super::result::EventError;
+use super::result::EventResult;
+
+// Sent by the server once a timed game runs out of time. Between rounds the
+// game ends right away, otherwise it ends when the current round does.
+pub fn time_limit_reached(mut state: RoomState) -> EventResult {
+  let Some(meta) = state.get_meta_mut() else {
+    return EventResult {
+      error: Some(EventError::IncompatibleState(
+        state.to_string(),
+        RoomEvent::TimeLimitReached,
+      )),
+      result: state,
+    };
+  };
+  meta.time_limit_reached = true;
+
+  match state {
+    RoomState::RoundSummary(summary) if summary.meta.is_game_finished() => {
+      EventResult::ok(RoomState::GameOver(GameOver::from(summary)))
+    }
+    state => EventResult::ok(state),
+  }
+}
@@ -0,0 +1,50 @@
+use serde::Deserialize;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::state::data::PlayerId;
+use crate::state::data::RoomState;
+
+use super::apply_event::RoomEvent;
+use super::result::EventError;
+use super::result::EventResult;
+
+#[derive(Error, Debug)]
+pub enum TransferHostError {
+  #[error("Player {:?} is not a connected player", .0)]
+  InvalidHost(PlayerId),
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TransferHost {
+  pub host_id: PlayerId,
+  pub player_id: PlayerId,
+}
+
+// Hosting can only be handed to a connected player, never to a spectator
+pub fn transfer_host(mut state: RoomState, event: TransferHost) -> EventResult {
+  let TransferHost { host_id, player_id } = event;
+  let Some(meta) = state.get_meta_mut() else {
+    return EventResult {
+      error: Some(EventError::IncompatibleState(
+        state.to_string(),
+        RoomEvent::TransferHost(event),
+      )),
+      result: state,
+    };
+  };
+
+  if let Err(error) = meta.check_host(host_id) {
+    return EventResult::err(state, error);
+  }
+  let is_connected_player = meta
+    .player_info
+    .get(&player_id)
+    .is_some_and(|info| info.player_connected);
+  if !is_connected_player {
+    return EventResult::err(state, TransferHostError::InvalidHost(player_id));
+  }
+
+  meta.host = Some(player_id);
+  EventResult::ok(state)
+}
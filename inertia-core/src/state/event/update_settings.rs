@@ -0,0 +1,70 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::board_generators::GeneratorSpec;
+use crate::state::data::GameLength;
+use crate::state::data::PlayerId;
+use crate::state::data::RoomState;
+use crate::state::data::RoundSummary;
+use crate::state::data::RoundTimers;
+use crate::state::scoring::ScoringPolicy;
+use crate::state::view::SolutionVisibility;
+
+use super::result::EventResult;
+
+// Settings left as None are kept as they are
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UpdateSettings {
+  pub host_id: PlayerId,
+  // Only the spec is kept here. Whoever sends the event builds the generator
+  // from it, since boards are drawn outside the state machine.
+  pub generator_spec: Option<GeneratorSpec>,
+  pub timers: Option<RoundTimers>,
+  pub scoring: Option<ScoringPolicy>,
+  pub game_length: Option<GameLength>,
+  pub solution_visibility: Option<SolutionVisibility>,
+}
+
+// Settings can only change between rounds, and take effect from the next one
+pub fn round_summary_update_settings(
+  mut state: RoundSummary,
+  event: UpdateSettings,
+) -> EventResult {
+  let UpdateSettings {
+    host_id,
+    generator_spec,
+    timers,
+    scoring,
+    game_length,
+    solution_visibility,
+  } = event;
+  if let Err(error) = state.meta.check_host(host_id) {
+    return EventResult::err(RoomState::RoundSummary(state), error);
+  }
+  if let Some(Err(error)) = timers.map(|timers| timers.validate()) {
+    return EventResult::err(RoomState::RoundSummary(state), error);
+  }
+  if let Some(Err(error)) = game_length.map(|length| length.validate()) {
+    return EventResult::err(RoomState::RoundSummary(state), error);
+  }
+
+  if let Some(generator_spec) = generator_spec {
+    if generator_spec != state.meta.generator_spec {
+      state.meta.generator_spec = generator_spec;
+      state.meta.boards_drawn = 0;
+    }
+  }
+  if let Some(timers) = timers {
+    state.meta.timers = timers;
+  }
+  if let Some(scoring) = scoring {
+    state.meta.scoring = scoring;
+  }
+  if let Some(game_length) = game_length {
+    state.meta.game_length = game_length;
+  }
+  if let Some(solution_visibility) = solution_visibility {
+    state.meta.solution_visibility = solution_visibility;
+  }
+  EventResult::ok(RoomState::RoundSummary(state))
+}
@@ -1,10 +1,12 @@
+use serde::Deserialize;
+use serde::Serialize;
 use thiserror::Error;
 
 use crate::solvers::Solution;
 use crate::state::data::RoomState;
 use crate::state::data::RoundSolving;
-use crate::state::data::RoundSummary;
 
+use super::finish_round::finish_round;
 use super::result::EventResult;
 
 #[derive(Error, Debug)]
@@ -13,7 +15,7 @@ pub enum UpdateSolutionError {
   SolutionExceedsBid,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct UpdateSolution {
   pub solution: Solution,
 }
@@ -23,7 +25,7 @@ pub fn round_solving_update_solution(
   event: UpdateSolution,
 ) -> EventResult {
   let RoundSolving {
-    mut meta,
+    meta,
     board,
     player_bids,
     solver,
@@ -52,17 +54,13 @@ pub fn round_solving_update_solution(
   }
 
   if board.is_solution(&updated_solution) {
-    meta
-      .player_info
-      .entry(solver)
-      .and_modify(|info| info.player_score += 1);
-    return EventResult::ok(RoomState::RoundSummary(RoundSummary {
+    return EventResult::ok(finish_round(
       meta,
-      last_round_board: Some(board),
-      last_round_optimal_solution: Some(optimal_solution),
-      last_round_solution: Some(updated_solution),
-      last_solver: Some(solver),
-    }));
+      board,
+      optimal_solution,
+      &player_bids,
+      Some((solver, updated_solution)),
+    ));
   }
 
   EventResult::ok(RoomState::RoundSolving(RoundSolving {
@@ -2,8 +2,8 @@ use crate::solvers::Solution;
 use crate::state::data::PlayerId;
 use crate::state::data::RoomState;
 use crate::state::data::RoundSolving;
-use crate::state::data::RoundSummary;
 
+use super::finish_round::finish_round;
 use super::result::EventResult;
 
 #[derive(Debug)]
@@ -36,12 +36,12 @@ pub fn round_solving_yield_solve(state: RoundSolving) -> EventResult {
         solution: Solution(Vec::new()),
       }))
     }
-    None => EventResult::ok(RoomState::RoundSummary(RoundSummary {
+    None => EventResult::ok(finish_round(
       meta,
-      last_round_board: Some(board),
-      last_round_optimal_solution: Some(optimal_solution),
-      last_round_solution: None,
-      last_solver: None,
-    })),
+      board,
+      optimal_solution,
+      &player_bids,
+      None,
+    )),
   }
 }
@@ -0,0 +1,75 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::state::data::RoomState;
+use crate::state::event::apply_event::RoomEvent;
+use crate::state::snapshot::RoomSnapshot;
+use crate::state::snapshot::SnapshotVersionError;
+
+// An event that was applied to a room, and when, in milliseconds since the
+// unix epoch
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LoggedEvent {
+  pub at_millis: u64,
+  pub event: RoomEvent,
+}
+
+// The most recent events applied to a room, for auditing rooms and
+// reproducing bugs. The room's state can be rebuilt at any time by replaying
+// the events from the snapshot taken at the start. Once the log is full it
+// starts over from the room's current state, so it only covers the last
+// MAX_EVENTS events. The server writes a room's log to its trace logs when the
+// room closes.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct EventLog {
+  initial: RoomSnapshot,
+  events: Vec<LoggedEvent>,
+}
+
+impl EventLog {
+  pub const MAX_EVENTS: usize = 1000;
+
+  pub fn new(initial: RoomState) -> Self {
+    EventLog {
+      initial: RoomSnapshot::from(initial),
+      events: Vec::new(),
+    }
+  }
+
+  pub fn initial_state(&self) -> Result<RoomState, SnapshotVersionError> {
+    RoomState::try_from(self.initial.clone())
+  }
+
+  pub fn events(&self) -> &[LoggedEvent] {
+    &self.events
+  }
+
+  pub fn push(&mut self, at_millis: u64, event: RoomEvent) {
+    if self.events.len() >= Self::MAX_EVENTS {
+      self.roll_over();
+    }
+    self.events.push(LoggedEvent { at_millis, event });
+  }
+
+  fn roll_over(&mut self) {
+    match self.replay() {
+      Ok(state) => *self = EventLog::new(state),
+      // The log can't be replayed anyway, so there's nothing to keep
+      Err(_) => self.events.clear(),
+    }
+  }
+
+  // Events that failed to apply left the room as it was, so they replay the
+  // same way whether or not they were logged
+  pub fn replay(&self) -> Result<RoomState, SnapshotVersionError> {
+    Ok(
+      self
+        .events
+        .iter()
+        .cloned()
+        .fold(self.initial_state()?, |state, logged| {
+          state.apply_at(logged.event, logged.at_millis).result
+        }),
+    )
+  }
+}
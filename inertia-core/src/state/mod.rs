@@ -1,11 +1,16 @@
 pub mod data;
 pub mod event;
+pub mod event_log;
+pub mod scoring;
+pub mod snapshot;
+pub mod view;
 
 #[cfg(test)]
 mod test {
   use pretty_assertions::assert_eq;
   use std::collections::HashMap;
 
+  use crate::board_generators::GeneratorSpec;
   use crate::board_generators::OneMoveSolutionBoardGenerator;
   use crate::mechanics::Direction;
   use crate::mechanics::Position;
@@ -13,23 +18,54 @@ mod test {
   use crate::mechanics::SolvedPositionGenerator;
   use crate::solvers::Solution;
   use crate::solvers::SolutionStep;
+  use crate::state::data::GameLength;
+  use crate::state::data::GameOver;
+  use crate::state::data::NotHostError;
   use crate::state::data::PlayerBids;
   use crate::state::data::PlayerId;
   use crate::state::data::PlayerInfo;
   use crate::state::data::PlayerName;
   use crate::state::data::PlayerReconnectKey;
+  use crate::state::data::PlayerRole;
   use crate::state::data::RoomId;
   use crate::state::data::RoomMeta;
   use crate::state::data::RoomState;
+  use crate::state::data::RoundResult;
   use crate::state::data::RoundSolving;
   use crate::state::data::RoundStart;
   use crate::state::data::RoundSummary;
+  use crate::state::data::RoundTimerError;
+  use crate::state::data::RoundTimers;
+  use crate::state::data::RoundTimings;
+  use crate::state::data::SpectatorError;
   use crate::state::event::apply_event::RoomEvent;
   use crate::state::event::connect::Connect;
+  use crate::state::event::connect::ConnectError;
+  use crate::state::event::connect::PlayerNameError;
   use crate::state::event::disconnect::Disconnect;
+  use crate::state::event::end_game::EndGame;
+  use crate::state::event::kick_player::KickPlayer;
+  use crate::state::event::kick_player::KickPlayerError;
+  use crate::state::event::new_game::NewGame;
+  use crate::state::event::rename::Rename;
+  use crate::state::event::rename::RenameError;
+  use crate::state::event::result::EventError;
+  use crate::state::event::set_role::SetRole;
+  use crate::state::event::start_round::StartRound;
+  use crate::state::event::transfer_host::TransferHost;
+  use crate::state::event::transfer_host::TransferHostError;
+  use crate::state::event::update_settings::UpdateSettings;
+  use crate::state::event_log::EventLog;
+  use crate::state::scoring::ScoringPolicy;
+  use crate::state::snapshot::RoomSnapshot;
+  use crate::state::snapshot::SnapshotVersionError;
+  use crate::state::snapshot::SNAPSHOT_VERSION;
+  use crate::state::view::SolutionVisibility;
+  use crate::state::view::ViewerRole;
 
   use super::data::PlayerBid;
   use super::event::make_bid::MakeBid;
+  use super::event::ready_bid::ReadyBid;
   use super::event::update_solution::UpdateSolution;
 
   fn expected_board() -> Position {
@@ -42,8 +78,13 @@ mod test {
       .solution
   }
 
-  fn expected_generator() -> Box<dyn SolvedPositionGenerator> {
-    Box::new(OneMoveSolutionBoardGenerator::new())
+  fn start_round_event(player_id: PlayerId) -> RoomEvent {
+    RoomEvent::StartRound(StartRound {
+      player_id,
+      solved_position: Some(Box::new(
+        OneMoveSolutionBoardGenerator::new().generate_solved_position(),
+      )),
+    })
   }
 
   fn expected_room() -> RoomId {
@@ -51,8 +92,13 @@ mod test {
   }
 
   fn simulate(events: Vec<RoomEvent>) -> RoomState {
-    let state =
-      RoomState::initial(RoomId(0), OneMoveSolutionBoardGenerator::new());
+    let state = RoomState::initial(
+      RoomId(0),
+      GeneratorSpec::OneMove,
+      RoundTimers::default(),
+      ScoringPolicy::default(),
+      GameLength::default(),
+    );
     simulate_on(state, events)
   }
 
@@ -73,14 +119,27 @@ mod test {
       RoomState::RoundSummary(RoundSummary {
         meta: RoomMeta {
           room_id: expected_room(),
-          generator: expected_generator(),
+          generator_spec: GeneratorSpec::OneMove,
+          boards_drawn: 0,
+          timers: RoundTimers::default(),
+          scoring: ScoringPolicy::default(),
+          game_length: GameLength::default(),
+          solution_visibility: SolutionVisibility::default(),
+          time_limit_reached: false,
+          game_started_at: None,
+          round_timings: RoundTimings::default(),
+          round_history: Vec::new(),
           player_info: HashMap::new(),
+          spectator_info: HashMap::new(),
+          kicked_players: HashMap::new(),
+          host: None,
           round_number: 0
         },
         last_round_board: None,
         last_round_solution: None,
         last_solver: None,
-        last_round_optimal_solution: None
+        last_round_optimal_solution: None,
+        last_round_score_deltas: HashMap::new(),
       })
     );
   }
@@ -91,6 +150,7 @@ mod test {
       player_name: PlayerName::from("test"),
       player_id: PlayerId(1),
       player_reconnect_key: PlayerReconnectKey(123),
+      role: PlayerRole::Player,
     })];
     let result = simulate(events);
     assert_eq!(
@@ -98,7 +158,16 @@ mod test {
       RoomState::RoundSummary(RoundSummary {
         meta: RoomMeta {
           room_id: expected_room(),
-          generator: expected_generator(),
+          generator_spec: GeneratorSpec::OneMove,
+          boards_drawn: 0,
+          timers: RoundTimers::default(),
+          scoring: ScoringPolicy::default(),
+          game_length: GameLength::default(),
+          solution_visibility: SolutionVisibility::default(),
+          time_limit_reached: false,
+          game_started_at: None,
+          round_timings: RoundTimings::default(),
+          round_history: Vec::new(),
           player_info: HashMap::from([(
             PlayerId(1),
             PlayerInfo {
@@ -110,12 +179,16 @@ mod test {
               player_score: 0
             }
           )]),
+          spectator_info: HashMap::new(),
+          kicked_players: HashMap::new(),
+          host: Some(PlayerId(1)),
           round_number: 0
         },
         last_round_board: None,
         last_round_solution: None,
         last_solver: None,
-        last_round_optimal_solution: None
+        last_round_optimal_solution: None,
+        last_round_score_deltas: HashMap::new(),
       })
     );
   }
@@ -127,18 +200,21 @@ mod test {
         player_name: PlayerName::from("test"),
         player_id: PlayerId(1),
         player_reconnect_key: PlayerReconnectKey(123),
+        role: PlayerRole::Player,
       }),
       // Duplicate username, different ID
       RoomEvent::Connect(Connect {
         player_name: PlayerName::from("test"),
         player_id: PlayerId(2),
         player_reconnect_key: PlayerReconnectKey(123),
+        role: PlayerRole::Player,
       }),
       // Same username and player ID, different reconnect key
       RoomEvent::Connect(Connect {
         player_name: PlayerName::from("test"),
         player_id: PlayerId(1),
         player_reconnect_key: PlayerReconnectKey(321),
+        role: PlayerRole::Player,
       }),
     ];
     let result = simulate(events);
@@ -147,7 +223,16 @@ mod test {
       RoomState::RoundSummary(RoundSummary {
         meta: RoomMeta {
           room_id: expected_room(),
-          generator: expected_generator(),
+          generator_spec: GeneratorSpec::OneMove,
+          boards_drawn: 0,
+          timers: RoundTimers::default(),
+          scoring: ScoringPolicy::default(),
+          game_length: GameLength::default(),
+          solution_visibility: SolutionVisibility::default(),
+          time_limit_reached: false,
+          game_started_at: None,
+          round_timings: RoundTimings::default(),
+          round_history: Vec::new(),
           player_info: HashMap::from([(
             PlayerId(1),
             PlayerInfo {
@@ -159,12 +244,16 @@ mod test {
               player_score: 0
             }
           )]),
+          spectator_info: HashMap::new(),
+          kicked_players: HashMap::new(),
+          host: Some(PlayerId(1)),
           round_number: 0
         },
         last_round_board: None,
         last_round_solution: None,
         last_solver: None,
-        last_round_optimal_solution: None
+        last_round_optimal_solution: None,
+        last_round_score_deltas: HashMap::new(),
       })
     );
   }
@@ -176,16 +265,18 @@ mod test {
         player_name: PlayerName::from("test"),
         player_id: PlayerId(1),
         player_reconnect_key: PlayerReconnectKey(123),
+        role: PlayerRole::Player,
       }),
       RoomEvent::Connect(Connect {
         player_name: PlayerName::from("test2"),
         player_id: PlayerId(2),
         player_reconnect_key: PlayerReconnectKey(321),
+        role: PlayerRole::Player,
       }),
       RoomEvent::SoftDisconnect(Disconnect {
         player_id: PlayerId(2),
       }),
-      RoomEvent::StartRound,
+      start_round_event(PlayerId(1)),
     ];
     let result = simulate(events);
     assert_eq!(
@@ -193,7 +284,16 @@ mod test {
       RoomState::RoundStart(RoundStart {
         meta: RoomMeta {
           room_id: expected_room(),
-          generator: expected_generator(),
+          generator_spec: GeneratorSpec::OneMove,
+          boards_drawn: 1,
+          timers: RoundTimers::default(),
+          scoring: ScoringPolicy::default(),
+          game_length: GameLength::default(),
+          solution_visibility: SolutionVisibility::default(),
+          time_limit_reached: false,
+          game_started_at: None,
+          round_timings: RoundTimings::default(),
+          round_history: Vec::new(),
           player_info: HashMap::from([
             (
               PlayerId(1),
@@ -218,6 +318,9 @@ mod test {
               }
             )
           ]),
+          spectator_info: HashMap::new(),
+          kicked_players: HashMap::new(),
+          host: Some(PlayerId(1)),
           round_number: 1
         },
         board: expected_board(),
@@ -233,6 +336,7 @@ mod test {
         player_name: PlayerName::from("test"),
         player_id: PlayerId(1),
         player_reconnect_key: PlayerReconnectKey(123),
+        role: PlayerRole::Player,
       }),
       RoomEvent::SoftDisconnect(Disconnect {
         player_id: PlayerId(1),
@@ -249,8 +353,9 @@ mod test {
         player_name: PlayerName::from("test"),
         player_id: PlayerId(1),
         player_reconnect_key: PlayerReconnectKey(123),
+        role: PlayerRole::Player,
       }),
-      RoomEvent::StartRound,
+      start_round_event(PlayerId(1)),
     ];
     let result = simulate(events);
     assert_eq!(
@@ -258,7 +363,16 @@ mod test {
       RoomState::RoundStart(RoundStart {
         meta: RoomMeta {
           room_id: expected_room(),
-          generator: expected_generator(),
+          generator_spec: GeneratorSpec::OneMove,
+          boards_drawn: 1,
+          timers: RoundTimers::default(),
+          scoring: ScoringPolicy::default(),
+          game_length: GameLength::default(),
+          solution_visibility: SolutionVisibility::default(),
+          time_limit_reached: false,
+          game_started_at: None,
+          round_timings: RoundTimings::default(),
+          round_history: Vec::new(),
           player_info: HashMap::from([(
             PlayerId(1),
             PlayerInfo {
@@ -270,6 +384,9 @@ mod test {
               player_score: 0
             }
           )]),
+          spectator_info: HashMap::new(),
+          kicked_players: HashMap::new(),
+          host: Some(PlayerId(1)),
           round_number: 1
         },
         board: expected_board(),
@@ -285,18 +402,21 @@ mod test {
         player_name: PlayerName::from("test"),
         player_id: PlayerId(1),
         player_reconnect_key: PlayerReconnectKey(123),
+        role: PlayerRole::Player,
       }),
       RoomEvent::Connect(Connect {
         player_name: PlayerName::from("test2"),
         player_id: PlayerId(2),
         player_reconnect_key: PlayerReconnectKey(123),
+        role: PlayerRole::Player,
       }),
       RoomEvent::Connect(Connect {
         player_name: PlayerName::from("test3"),
         player_id: PlayerId(3),
         player_reconnect_key: PlayerReconnectKey(123),
+        role: PlayerRole::Player,
       }),
-      RoomEvent::StartRound,
+      start_round_event(PlayerId(1)),
       RoomEvent::MakeBid(MakeBid {
         player_id: PlayerId(3),
         bid_value: 5,
@@ -315,7 +435,16 @@ mod test {
 
     let expected_meta = RoomMeta {
       room_id: expected_room(),
-      generator: expected_generator(),
+      generator_spec: GeneratorSpec::OneMove,
+      boards_drawn: 1,
+      timers: RoundTimers::default(),
+      scoring: ScoringPolicy::default(),
+      game_length: GameLength::default(),
+      solution_visibility: SolutionVisibility::default(),
+      time_limit_reached: false,
+      game_started_at: None,
+      round_timings: RoundTimings::default(),
+      round_history: Vec::new(),
       player_info: HashMap::from([
         (
           PlayerId(1),
@@ -351,6 +480,9 @@ mod test {
           },
         ),
       ]),
+      spectator_info: HashMap::new(),
+      kicked_players: HashMap::new(),
+      host: Some(PlayerId(1)),
       round_number: 1,
     };
     let mut expected = RoundSolving {
@@ -388,14 +520,30 @@ mod test {
     assert_eq!(result, RoomState::RoundSolving(expected.clone()));
 
     let result = simulate_on(result, vec![RoomEvent::YieldSolve]);
+    let mut expected_meta = expected_meta;
+    expected
+      .player_bids
+      .bids
+      .insert(PlayerId(2), PlayerBid::Failed { value: 5 });
+    expected_meta.round_history.push(RoundResult {
+      round_number: 1,
+      board: expected_board(),
+      optimal_solution: expected_optimal_solution(),
+      player_bids: expected.player_bids,
+      solver: None,
+      solution: None,
+      score_deltas: HashMap::new(),
+      timings: RoundTimings::default(),
+    });
     assert_eq!(
       result,
       RoomState::RoundSummary(RoundSummary {
-        meta: expected_meta.clone(),
+        meta: expected_meta,
         last_round_board: Some(expected_board()),
         last_round_optimal_solution: Some(expected_optimal_solution()),
         last_round_solution: None,
         last_solver: None,
+        last_round_score_deltas: HashMap::new(),
       })
     );
   }
@@ -407,8 +555,9 @@ mod test {
         player_name: PlayerName::from("test"),
         player_id: PlayerId(1),
         player_reconnect_key: PlayerReconnectKey(123),
+        role: PlayerRole::Player,
       }),
-      RoomEvent::StartRound,
+      start_round_event(PlayerId(1)),
       RoomEvent::MakeBid(MakeBid {
         player_id: PlayerId(1),
         bid_value: 1,
@@ -428,7 +577,34 @@ mod test {
       RoomState::RoundSummary(RoundSummary {
         meta: RoomMeta {
           room_id: expected_room(),
-          generator: expected_generator(),
+          generator_spec: GeneratorSpec::OneMove,
+          boards_drawn: 1,
+          timers: RoundTimers::default(),
+          scoring: ScoringPolicy::default(),
+          game_length: GameLength::default(),
+          solution_visibility: SolutionVisibility::default(),
+          time_limit_reached: false,
+          game_started_at: None,
+          round_timings: RoundTimings::default(),
+          round_history: vec![RoundResult {
+            round_number: 1,
+            board: expected_board(),
+            optimal_solution: expected_optimal_solution(),
+            player_bids: PlayerBids {
+              bids: HashMap::from([(
+                PlayerId(1),
+                PlayerBid::Prospective { value: 1, order: 0 }
+              )]),
+              timestamp: 1,
+            },
+            solver: Some(PlayerId(1)),
+            solution: Some(Solution(vec![SolutionStep {
+              actor: 0,
+              direction: Direction::Down,
+            }])),
+            score_deltas: HashMap::from([(PlayerId(1), 1)]),
+            timings: RoundTimings::default(),
+          }],
           player_info: HashMap::from([(
             PlayerId(1),
             PlayerInfo {
@@ -440,6 +616,9 @@ mod test {
               player_score: 1
             }
           )]),
+          spectator_info: HashMap::new(),
+          kicked_players: HashMap::new(),
+          host: Some(PlayerId(1)),
           round_number: 1
         },
         last_round_board: Some(expected_board()),
@@ -448,8 +627,868 @@ mod test {
           actor: 0,
           direction: Direction::Down,
         }])),
-        last_solver: Some(PlayerId(1))
+        last_solver: Some(PlayerId(1)),
+        last_round_score_deltas: HashMap::from([(PlayerId(1), 1)]),
+      })
+    );
+  }
+
+  #[test]
+  fn no_boards_left_ends_game() {
+    let events = vec![
+      RoomEvent::Connect(Connect {
+        player_name: PlayerName::from("test"),
+        player_id: PlayerId(1),
+        player_reconnect_key: PlayerReconnectKey(123),
+        role: PlayerRole::Player,
+      }),
+      start_round_event(PlayerId(1)),
+      RoomEvent::FinalizeBids,
+      RoomEvent::StartRound(StartRound {
+        player_id: PlayerId(1),
+        solved_position: None,
+      }),
+    ];
+    let result = simulate(events);
+
+    let RoomState::GameOver(GameOver {
+      meta,
+      last_round_board,
+      last_round_optimal_solution,
+      ..
+    }) = &result
+    else {
+      panic!("Expected the game to be over, got {}", result);
+    };
+    assert_eq!(meta.round_number, 1);
+    assert_eq!(*last_round_board, Some(expected_board()));
+    assert_eq!(
+      *last_round_optimal_solution,
+      Some(expected_optimal_solution())
+    );
+
+    let after = result.clone().apply(start_round_event(PlayerId(1)));
+    assert!(after.error.is_some());
+    assert_eq!(after.result, result);
+  }
+
+  #[test]
+  fn rename() {
+    let events = vec![
+      RoomEvent::Connect(Connect {
+        player_name: PlayerName::from("test"),
+        player_id: PlayerId(1),
+        player_reconnect_key: PlayerReconnectKey(123),
+        role: PlayerRole::Player,
+      }),
+      RoomEvent::Rename(Rename {
+        player_id: PlayerId(1),
+        player_name: PlayerName::from("renamed"),
+      }),
+      // Free to take the old name now
+      RoomEvent::Connect(Connect {
+        player_name: PlayerName::from("test"),
+        player_id: PlayerId(2),
+        player_reconnect_key: PlayerReconnectKey(321),
+        role: PlayerRole::Player,
+      }),
+    ];
+    let result = simulate(events);
+    assert_eq!(
+      result,
+      RoomState::RoundSummary(RoundSummary {
+        meta: RoomMeta {
+          room_id: expected_room(),
+          generator_spec: GeneratorSpec::OneMove,
+          boards_drawn: 0,
+          timers: RoundTimers::default(),
+          scoring: ScoringPolicy::default(),
+          game_length: GameLength::default(),
+          solution_visibility: SolutionVisibility::default(),
+          time_limit_reached: false,
+          game_started_at: None,
+          round_timings: RoundTimings::default(),
+          round_history: Vec::new(),
+          player_info: HashMap::from([
+            (
+              PlayerId(1),
+              PlayerInfo {
+                player_id: PlayerId(1),
+                player_name: PlayerName::from("renamed"),
+                player_reconnect_key: PlayerReconnectKey(123),
+                player_last_seen: 0,
+                player_connected: true,
+                player_score: 0
+              }
+            ),
+            (
+              PlayerId(2),
+              PlayerInfo {
+                player_id: PlayerId(2),
+                player_name: PlayerName::from("test"),
+                player_reconnect_key: PlayerReconnectKey(321),
+                player_last_seen: 0,
+                player_connected: true,
+                player_score: 0
+              }
+            )
+          ]),
+          spectator_info: HashMap::new(),
+          kicked_players: HashMap::new(),
+          host: Some(PlayerId(1)),
+          round_number: 0
+        },
+        last_round_board: None,
+        last_round_solution: None,
+        last_solver: None,
+        last_round_optimal_solution: None,
+        last_round_score_deltas: HashMap::new(),
       })
     );
   }
+
+  #[test]
+  fn bad_rename() {
+    let state = simulate(vec![
+      RoomEvent::Connect(Connect {
+        player_name: PlayerName::from("test"),
+        player_id: PlayerId(1),
+        player_reconnect_key: PlayerReconnectKey(123),
+        role: PlayerRole::Player,
+      }),
+      RoomEvent::Connect(Connect {
+        player_name: PlayerName::from("test2"),
+        player_id: PlayerId(2),
+        player_reconnect_key: PlayerReconnectKey(321),
+        role: PlayerRole::Player,
+      }),
+      start_round_event(PlayerId(1)),
+    ]);
+
+    let rename = |player_id, player_name| {
+      state.clone().apply(RoomEvent::Rename(Rename {
+        player_id: PlayerId(player_id),
+        player_name: PlayerName::from(player_name),
+      }))
+    };
+
+    let taken = rename(1, "test2");
+    assert!(matches!(
+      taken.error,
+      Some(EventError::RenameError(RenameError::PlayerNameError(
+        PlayerNameError::UsernameTaken(_)
+      )))
+    ));
+    assert_eq!(taken.result, state);
+
+    let empty = rename(1, "");
+    assert!(matches!(
+      empty.error,
+      Some(EventError::RenameError(RenameError::PlayerNameError(
+        PlayerNameError::InvalidName(_)
+      )))
+    ));
+    assert_eq!(empty.result, state);
+
+    let unknown = rename(3, "test3");
+    assert!(matches!(
+      unknown.error,
+      Some(EventError::RenameError(RenameError::UnknownPlayer(
+        PlayerId(3)
+      )))
+    ));
+    assert_eq!(unknown.result, state);
+  }
+
+  fn connect_player_and_spectator() -> Vec<RoomEvent> {
+    vec![
+      RoomEvent::Connect(Connect {
+        player_name: PlayerName::from("player"),
+        player_id: PlayerId(1),
+        player_reconnect_key: PlayerReconnectKey(123),
+        role: PlayerRole::Player,
+      }),
+      RoomEvent::Connect(Connect {
+        player_name: PlayerName::from("spectator"),
+        player_id: PlayerId(2),
+        player_reconnect_key: PlayerReconnectKey(321),
+        role: PlayerRole::Spectator,
+      }),
+    ]
+  }
+
+  #[test]
+  fn spectators_do_not_play() {
+    let state = simulate(connect_player_and_spectator());
+    let meta = state.get_meta().unwrap();
+    assert_eq!(meta.player_info.keys().collect::<Vec<_>>(), [&PlayerId(1)]);
+    assert_eq!(
+      meta.spectator_info.keys().collect::<Vec<_>>(),
+      [&PlayerId(2)]
+    );
+
+    let state = simulate_on(
+      state,
+      vec![
+        start_round_event(PlayerId(1)),
+        RoomEvent::MakeBid(MakeBid {
+          player_id: PlayerId(1),
+          bid_value: 1,
+        }),
+      ],
+    );
+    let spectator_bid = state.clone().apply(RoomEvent::MakeBid(MakeBid {
+      player_id: PlayerId(2),
+      bid_value: 1,
+    }));
+    assert!(matches!(
+      spectator_bid.error,
+      Some(EventError::SpectatorError(SpectatorError(PlayerId(2))))
+    ));
+    assert_eq!(spectator_bid.result, state);
+
+    // Only the player needs to be ready
+    let result = simulate_on(
+      state,
+      vec![RoomEvent::ReadyBid(ReadyBid {
+        player_id: PlayerId(1),
+      })],
+    );
+    assert_eq!(result.get_solver(), Some(PlayerId(1)));
+  }
+
+  #[test]
+  fn set_role_between_rounds() {
+    let mut events = connect_player_and_spectator();
+    events.push(RoomEvent::SetRole(SetRole {
+      player_id: PlayerId(2),
+      role: PlayerRole::Player,
+    }));
+    events.push(RoomEvent::SetRole(SetRole {
+      player_id: PlayerId(1),
+      role: PlayerRole::Spectator,
+    }));
+    let state = simulate(events);
+    let meta = state.get_meta().unwrap();
+    assert_eq!(meta.get_role(PlayerId(1)), Some(PlayerRole::Spectator));
+    assert_eq!(meta.get_role(PlayerId(2)), Some(PlayerRole::Player));
+    assert_eq!(meta.host, Some(PlayerId(2)));
+
+    let state = simulate_on(state, vec![start_round_event(PlayerId(2))]);
+    let result = state.clone().apply(RoomEvent::SetRole(SetRole {
+      player_id: PlayerId(1),
+      role: PlayerRole::Player,
+    }));
+    assert!(matches!(
+      result.error,
+      Some(EventError::IncompatibleState(..))
+    ));
+    assert_eq!(result.result, state);
+  }
+
+  fn connect_players(count: u32) -> Vec<RoomEvent> {
+    (1..=count)
+      .map(|id| {
+        RoomEvent::Connect(Connect {
+          player_name: PlayerName::from(format!("test{}", id)),
+          player_id: PlayerId(id),
+          player_reconnect_key: PlayerReconnectKey(123),
+          role: PlayerRole::Player,
+        })
+      })
+      .collect()
+  }
+
+  #[test]
+  fn host_passes_on() {
+    let state = simulate(connect_players(3));
+    assert_eq!(state.get_meta().unwrap().host, Some(PlayerId(1)));
+
+    let state = simulate_on(
+      state,
+      vec![
+        RoomEvent::SoftDisconnect(Disconnect {
+          player_id: PlayerId(1),
+        }),
+        RoomEvent::Connect(Connect {
+          player_name: PlayerName::from("test1"),
+          player_id: PlayerId(1),
+          player_reconnect_key: PlayerReconnectKey(123),
+          role: PlayerRole::Player,
+        }),
+      ],
+    );
+    assert_eq!(state.get_meta().unwrap().host, Some(PlayerId(2)));
+
+    let state = simulate_on(
+      state,
+      vec![RoomEvent::TransferHost(TransferHost {
+        host_id: PlayerId(2),
+        player_id: PlayerId(3),
+      })],
+    );
+    assert_eq!(state.get_meta().unwrap().host, Some(PlayerId(3)));
+
+    let state = simulate_on(
+      state,
+      vec![RoomEvent::HardDisconnect(Disconnect {
+        player_id: PlayerId(3),
+      })],
+    );
+    assert_eq!(state.get_meta().unwrap().host, Some(PlayerId(1)));
+  }
+
+  #[test]
+  fn host_only_events() {
+    let state = simulate(connect_players(2));
+
+    let not_host = |event| {
+      let result = state.clone().apply(event);
+      assert!(matches!(
+        result.error,
+        Some(EventError::NotHostError(NotHostError(PlayerId(2))))
+      ));
+      assert_eq!(result.result, state);
+    };
+    not_host(start_round_event(PlayerId(2)));
+    not_host(RoomEvent::KickPlayer(KickPlayer {
+      host_id: PlayerId(2),
+      player_id: PlayerId(1),
+    }));
+    not_host(RoomEvent::TransferHost(TransferHost {
+      host_id: PlayerId(2),
+      player_id: PlayerId(2),
+    }));
+    not_host(RoomEvent::UpdateSettings(UpdateSettings {
+      host_id: PlayerId(2),
+      generator_spec: Some(GeneratorSpec::OneMove),
+      timers: None,
+      scoring: None,
+      game_length: None,
+      solution_visibility: None,
+    }));
+    not_host(RoomEvent::EndGame(EndGame {
+      host_id: PlayerId(2),
+    }));
+
+    let kick_self = state.clone().apply(RoomEvent::KickPlayer(KickPlayer {
+      host_id: PlayerId(1),
+      player_id: PlayerId(1),
+    }));
+    assert!(matches!(
+      kick_self.error,
+      Some(EventError::KickPlayerError(KickPlayerError::KickedSelf(_)))
+    ));
+
+    let transfer_to_unknown =
+      state.clone().apply(RoomEvent::TransferHost(TransferHost {
+        host_id: PlayerId(1),
+        player_id: PlayerId(3),
+      }));
+    assert!(matches!(
+      transfer_to_unknown.error,
+      Some(EventError::TransferHostError(
+        TransferHostError::InvalidHost(PlayerId(3))
+      ))
+    ));
+  }
+
+  #[test]
+  fn kick_solver() {
+    let mut events = connect_players(2);
+    events.extend([
+      start_round_event(PlayerId(1)),
+      RoomEvent::MakeBid(MakeBid {
+        player_id: PlayerId(2),
+        bid_value: 1,
+      }),
+      RoomEvent::MakeBid(MakeBid {
+        player_id: PlayerId(1),
+        bid_value: 2,
+      }),
+      RoomEvent::FinalizeBids,
+    ]);
+    let state = simulate(events);
+    assert_eq!(state.get_solver(), Some(PlayerId(2)));
+
+    let state = simulate_on(
+      state,
+      vec![RoomEvent::KickPlayer(KickPlayer {
+        host_id: PlayerId(1),
+        player_id: PlayerId(2),
+      })],
+    );
+    assert_eq!(state.get_solver(), Some(PlayerId(1)));
+    assert_eq!(state.get_meta().unwrap().get_role(PlayerId(2)), None);
+  }
+
+  #[test]
+  fn kicked_player_stays_out() {
+    let state = simulate_on(
+      simulate(connect_players(2)),
+      vec![RoomEvent::KickPlayer(KickPlayer {
+        host_id: PlayerId(1),
+        player_id: PlayerId(2),
+      })],
+    );
+
+    let rejoin = |player_id, player_reconnect_key| {
+      state.clone().apply(RoomEvent::Connect(Connect {
+        player_name: PlayerName::from("test2"),
+        player_id,
+        player_reconnect_key,
+        role: PlayerRole::Player,
+      }))
+    };
+    let same_player = rejoin(PlayerId(2), PlayerReconnectKey(123));
+    assert!(matches!(
+      same_player.error,
+      Some(EventError::ConnectError(ConnectError::Kicked(PlayerId(2))))
+    ));
+    let new_id = rejoin(PlayerId(3), PlayerReconnectKey(123));
+    assert!(matches!(
+      new_id.error,
+      Some(EventError::ConnectError(ConnectError::Kicked(PlayerId(3))))
+    ));
+    assert_eq!(
+      new_id.result.get_meta().unwrap().get_role(PlayerId(3)),
+      None
+    );
+  }
+
+  #[test]
+  fn kick_ends_bidding_when_others_are_ready() {
+    let mut events = connect_players(2);
+    events.extend([
+      start_round_event(PlayerId(1)),
+      RoomEvent::MakeBid(MakeBid {
+        player_id: PlayerId(1),
+        bid_value: 2,
+      }),
+      RoomEvent::ReadyBid(ReadyBid {
+        player_id: PlayerId(1),
+      }),
+      RoomEvent::KickPlayer(KickPlayer {
+        host_id: PlayerId(1),
+        player_id: PlayerId(2),
+      }),
+    ]);
+    let state = simulate(events);
+    assert_eq!(state.get_solver(), Some(PlayerId(1)));
+  }
+
+  #[test]
+  fn end_game_mid_round() {
+    let mut events = connect_players(1);
+    events.extend([
+      start_round_event(PlayerId(1)),
+      RoomEvent::EndGame(EndGame {
+        host_id: PlayerId(1),
+      }),
+    ]);
+    let result = simulate(events);
+
+    let RoomState::GameOver(GameOver {
+      last_round_board,
+      last_solver,
+      ..
+    }) = &result
+    else {
+      panic!("Expected the game to be over, got {}", result);
+    };
+    assert_eq!(*last_round_board, Some(expected_board()));
+    assert_eq!(*last_solver, None);
+  }
+
+  #[test]
+  fn update_timers() {
+    let state = simulate(connect_players(1));
+    let update_timers = |state: &RoomState, round_solving_secs| {
+      state
+        .clone()
+        .apply(RoomEvent::UpdateSettings(UpdateSettings {
+          host_id: PlayerId(1),
+          generator_spec: None,
+          timers: Some(RoundTimers {
+            round_start_secs: 30,
+            round_bidding_secs: 20,
+            round_solving_secs,
+          }),
+          scoring: None,
+          game_length: None,
+          solution_visibility: None,
+        }))
+    };
+
+    let too_short = update_timers(&state, 1);
+    assert!(matches!(
+      too_short.error,
+      Some(EventError::RoundTimerError(RoundTimerError(1)))
+    ));
+    assert_eq!(too_short.result, state);
+
+    let updated = update_timers(&state, 45);
+    assert!(updated.error.is_none());
+    assert_eq!(
+      updated.result.get_meta().unwrap().timers,
+      RoundTimers {
+        round_start_secs: 30,
+        round_bidding_secs: 20,
+        round_solving_secs: 45,
+      }
+    );
+    assert_eq!(
+      updated.result.get_meta().unwrap().generator_spec,
+      GeneratorSpec::OneMove
+    );
+
+    let state =
+      simulate_on(updated.result, vec![start_round_event(PlayerId(1))]);
+    let mid_round = update_timers(&state, 45);
+    assert!(matches!(
+      mid_round.error,
+      Some(EventError::IncompatibleState(..))
+    ));
+    assert_eq!(mid_round.result, state);
+  }
+
+  #[test]
+  fn failed_bid_penalty() {
+    let mut events = connect_players(1);
+    events.push(RoomEvent::UpdateSettings(UpdateSettings {
+      host_id: PlayerId(1),
+      generator_spec: None,
+      timers: None,
+      scoring: Some(ScoringPolicy::FailedBidPenalty { penalty: 1 }),
+      game_length: None,
+      solution_visibility: None,
+    }));
+    events.extend([
+      start_round_event(PlayerId(1)),
+      RoomEvent::MakeBid(MakeBid {
+        player_id: PlayerId(1),
+        bid_value: 1,
+      }),
+      RoomEvent::FinalizeBids,
+      RoomEvent::UpdateSolution(UpdateSolution {
+        solution: expected_optimal_solution(),
+      }),
+    ]);
+    let state = simulate(events);
+    let RoomState::RoundSummary(summary) = &state else {
+      panic!("Expected a round summary, got {}", state);
+    };
+    assert_eq!(
+      summary.last_round_score_deltas,
+      HashMap::from([(PlayerId(1), 1)])
+    );
+
+    let events = vec![
+      start_round_event(PlayerId(1)),
+      RoomEvent::MakeBid(MakeBid {
+        player_id: PlayerId(1),
+        bid_value: 1,
+      }),
+      RoomEvent::FinalizeBids,
+      RoomEvent::YieldSolve,
+    ];
+    let state = simulate_on(state, events);
+    let RoomState::RoundSummary(summary) = &state else {
+      panic!("Expected a round summary, got {}", state);
+    };
+    assert_eq!(
+      summary.last_round_score_deltas,
+      HashMap::from([(PlayerId(1), -1)])
+    );
+    assert_eq!(summary.meta.player_info[&PlayerId(1)].player_score, 0);
+  }
+
+  fn play_unsolved_round() -> Vec<RoomEvent> {
+    vec![start_round_event(PlayerId(1)), RoomEvent::FinalizeBids]
+  }
+
+  #[test]
+  fn game_ends_after_rounds() {
+    let mut events = connect_players(2);
+    events.push(RoomEvent::UpdateSettings(UpdateSettings {
+      host_id: PlayerId(1),
+      generator_spec: None,
+      timers: None,
+      scoring: None,
+      game_length: Some(GameLength::Rounds { rounds: 2 }),
+      solution_visibility: None,
+    }));
+    events.extend(play_unsolved_round());
+    let state = simulate(events);
+    assert!(matches!(state, RoomState::RoundSummary(_)));
+
+    let state = simulate_on(state, play_unsolved_round());
+    let RoomState::GameOver(game_over) = &state else {
+      panic!("Expected the game to be over, got {}", state);
+    };
+    assert_eq!(game_over.round_history.len(), 2);
+    assert_eq!(
+      game_over
+        .standings
+        .iter()
+        .map(|standing| (standing.rank, standing.player_id))
+        .collect::<Vec<_>>(),
+      [(1, PlayerId(1)), (1, PlayerId(2))]
+    );
+
+    let not_host = state.clone().apply(RoomEvent::NewGame(NewGame {
+      host_id: PlayerId(2),
+    }));
+    assert!(matches!(
+      not_host.error,
+      Some(EventError::NotHostError(NotHostError(PlayerId(2))))
+    ));
+
+    let state = simulate_on(
+      state,
+      vec![RoomEvent::NewGame(NewGame {
+        host_id: PlayerId(1),
+      })],
+    );
+    let meta = state.get_meta().unwrap();
+    assert!(matches!(state, RoomState::RoundSummary(_)));
+    assert_eq!(meta.round_number, 0);
+    assert!(meta.round_history.is_empty());
+    assert_eq!(meta.game_length, GameLength::Rounds { rounds: 2 });
+  }
+
+  #[test]
+  fn game_ends_on_points_with_standings() {
+    let mut events = connect_players(2);
+    events.push(RoomEvent::UpdateSettings(UpdateSettings {
+      host_id: PlayerId(1),
+      generator_spec: None,
+      timers: None,
+      scoring: None,
+      game_length: Some(GameLength::FirstToPoints { points: 1 }),
+      solution_visibility: None,
+    }));
+    events.extend([
+      start_round_event(PlayerId(1)),
+      RoomEvent::MakeBid(MakeBid {
+        player_id: PlayerId(2),
+        bid_value: 1,
+      }),
+      RoomEvent::FinalizeBids,
+      RoomEvent::UpdateSolution(UpdateSolution {
+        solution: expected_optimal_solution(),
+      }),
+    ]);
+    let state = simulate(events);
+    let RoomState::GameOver(game_over) = &state else {
+      panic!("Expected the game to be over, got {}", state);
+    };
+    assert_eq!(
+      game_over
+        .standings
+        .iter()
+        .map(|standing| (standing.rank, standing.player_id))
+        .collect::<Vec<_>>(),
+      [(1, PlayerId(2)), (2, PlayerId(1))]
+    );
+  }
+
+  #[test]
+  fn time_limit_ends_game_with_round() {
+    let mut events = connect_players(1);
+    events.push(RoomEvent::UpdateSettings(UpdateSettings {
+      host_id: PlayerId(1),
+      generator_spec: None,
+      timers: None,
+      scoring: None,
+      game_length: Some(GameLength::TimeLimit { minutes: 10 }),
+      solution_visibility: None,
+    }));
+    events
+      .extend([start_round_event(PlayerId(1)), RoomEvent::TimeLimitReached]);
+    let state = simulate(events);
+    assert!(matches!(state, RoomState::RoundStart(_)));
+
+    let state = simulate_on(state, vec![RoomEvent::FinalizeBids]);
+    assert!(matches!(state, RoomState::GameOver(_)));
+  }
+
+  #[test]
+  fn round_history_keeps_timings_and_recent_rounds() {
+    let mut state = simulate(connect_players(1));
+    let events = vec![
+      start_round_event(PlayerId(1)),
+      RoomEvent::MakeBid(MakeBid {
+        player_id: PlayerId(1),
+        bid_value: 1,
+      }),
+      RoomEvent::FinalizeBids,
+      RoomEvent::YieldSolve,
+    ];
+    for (now_millis, event) in (1000..).step_by(1000).zip(events) {
+      state = state.apply_at(event, now_millis).result;
+    }
+    let round_history = &state.get_meta().unwrap().round_history;
+    assert_eq!(
+      round_history[0].timings,
+      RoundTimings {
+        started_at: 1000,
+        bidding_started_at: Some(2000),
+        solving_started_at: Some(3000),
+        finished_at: Some(4000),
+      }
+    );
+    assert_eq!(
+      round_history[0].player_bids.bids,
+      HashMap::from([(PlayerId(1), PlayerBid::Failed { value: 1 })])
+    );
+
+    for _ in 0..RoundResult::MAX_KEPT {
+      state = simulate_on(state, play_unsolved_round());
+    }
+    let round_history = &state.get_meta().unwrap().round_history;
+    assert_eq!(round_history.len(), RoundResult::MAX_KEPT);
+    assert_eq!(round_history[0].round_number, 2);
+  }
+
+  #[test]
+  fn replay_event_log() {
+    let mut state = simulate(connect_players(2));
+    let mut log = EventLog::new(state.clone());
+    let events = vec![
+      start_round_event(PlayerId(1)),
+      RoomEvent::MakeBid(MakeBid {
+        player_id: PlayerId(2),
+        bid_value: 1,
+      }),
+      RoomEvent::FinalizeBids,
+      RoomEvent::UpdateSolution(UpdateSolution {
+        solution: Solution(vec![SolutionStep {
+          actor: 0,
+          direction: Direction::Down,
+        }]),
+      }),
+      start_round_event(PlayerId(1)),
+    ];
+
+    for (at_millis, event) in (1000..).step_by(1000).zip(events) {
+      let result = state.apply_at(event.clone(), at_millis);
+      assert!(result.error.is_none());
+      log.push(at_millis, event);
+      state = result.result;
+    }
+    assert_eq!(log.replay().unwrap(), state);
+  }
+
+  #[test]
+  fn event_log_rolls_over() {
+    let mut state = simulate(connect_players(2));
+    let mut log = EventLog::new(state.clone());
+    for index in 0..EventLog::MAX_EVENTS + 10 {
+      let event = RoomEvent::Rename(Rename {
+        player_id: PlayerId(1),
+        player_name: PlayerName::from(format!("player {}", index)),
+      });
+      state = state.apply(event.clone()).result;
+      log.push(0, event);
+    }
+    assert!(log.events().len() <= EventLog::MAX_EVENTS);
+    assert_eq!(log.replay().unwrap(), state);
+    assert_eq!(
+      state.get_meta().unwrap().player_info[&PlayerId(1)].player_name,
+      PlayerName::from(format!("player {}", EventLog::MAX_EVENTS + 9))
+    );
+  }
+
+  #[test]
+  fn snapshot_keeps_hidden_fields() {
+    let mut events = connect_players(2);
+    events.extend([
+      start_round_event(PlayerId(1)),
+      RoomEvent::MakeBid(MakeBid {
+        player_id: PlayerId(2),
+        bid_value: 4,
+      }),
+      RoomEvent::MakeBid(MakeBid {
+        player_id: PlayerId(1),
+        bid_value: 3,
+      }),
+    ]);
+    let state = simulate(events);
+
+    let snapshot = RoomSnapshot::from(state.clone());
+    assert_eq!(snapshot.version, SNAPSHOT_VERSION);
+    assert_eq!(RoomState::try_from(snapshot.clone()).unwrap(), state);
+
+    let future = RoomSnapshot {
+      version: SNAPSHOT_VERSION + 1,
+      ..snapshot
+    };
+    assert!(matches!(
+      RoomState::try_from(future),
+      Err(SnapshotVersionError(version)) if version == SNAPSHOT_VERSION + 1
+    ));
+  }
+
+  #[test]
+  fn views_hide_solution_from_others() {
+    let mut events = connect_players(2);
+    events.push(RoomEvent::Connect(Connect {
+      player_name: PlayerName::from("test3"),
+      player_id: PlayerId(3),
+      player_reconnect_key: PlayerReconnectKey(123),
+      role: PlayerRole::Spectator,
+    }));
+    events.extend([
+      RoomEvent::UpdateSettings(UpdateSettings {
+        host_id: PlayerId(1),
+        generator_spec: None,
+        timers: None,
+        scoring: None,
+        game_length: None,
+        solution_visibility: Some(SolutionVisibility::SolverOnly),
+      }),
+      start_round_event(PlayerId(1)),
+      RoomEvent::MakeBid(MakeBid {
+        player_id: PlayerId(2),
+        bid_value: 2,
+      }),
+      RoomEvent::FinalizeBids,
+      RoomEvent::UpdateSolution(UpdateSolution {
+        solution: Solution(vec![SolutionStep {
+          actor: 1,
+          direction: Direction::Up,
+        }]),
+      }),
+    ]);
+    let state = simulate(events);
+
+    let solver_view = state.view(PlayerId(2));
+    assert_eq!(solver_view.viewer_role, Some(ViewerRole::Solver));
+    assert_eq!(solver_view.hidden_moves, 0);
+    assert_eq!(solver_view.state, state);
+
+    let host_view = state.view(PlayerId(1));
+    assert_eq!(host_view.viewer_role, Some(ViewerRole::Host));
+    assert_eq!(host_view.hidden_moves, 1);
+    let RoomState::RoundSolving(RoundSolving { solution, .. }) =
+      &host_view.state
+    else {
+      panic!("Expected solving, got {}", host_view.state);
+    };
+    assert!(solution.0.is_empty());
+
+    let spectator_view = state.view(PlayerId(3));
+    assert_eq!(spectator_view.viewer_role, Some(ViewerRole::Spectator));
+    assert_eq!(spectator_view.hidden_moves, 1);
+    assert_eq!(state.view(PlayerId(4)).viewer_role, None);
+
+    let mut delayed = state.clone();
+    delayed.get_meta_mut().unwrap().solution_visibility =
+      SolutionVisibility::Delayed { moves: 1 };
+    assert_eq!(delayed.view(PlayerId(1)).hidden_moves, 1);
+    delayed.get_meta_mut().unwrap().solution_visibility =
+      SolutionVisibility::Everyone;
+    assert_eq!(delayed.view(PlayerId(1)).state, delayed);
+  }
 }
@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::solvers::get_solution_difficulty;
+use crate::solvers::Solution;
+use crate::state::data::PlayerBid;
+use crate::state::data::PlayerBids;
+use crate::state::data::PlayerId;
+use crate::state::data::RoomMeta;
+
+#[cfg(feature = "web")]
+use {tsify::Tsify, wasm_bindgen::prelude::wasm_bindgen};
+
+// How a room hands out points at the end of each round. The solver always
+// scores; policies differ in how much and whether anyone else is affected.
+#[derive(
+  Serialize, Deserialize, Default, Eq, PartialEq, Copy, Clone, Debug,
+)]
+#[cfg_attr(feature = "web", derive(Tsify), tsify(into_wasm_abi, from_wasm_abi))]
+#[serde(tag = "type", content = "content")]
+pub enum ScoringPolicy {
+  // One point for solving
+  #[default]
+  Classic,
+  // One point for solving, plus a bonus for matching the optimal length
+  OptimalBonus {
+    bonus: u16,
+  },
+  // One point for solving, and a penalty for every bid that failed
+  FailedBidPenalty {
+    penalty: u16,
+  },
+  // From one point for the easiest boards up to five for the hardest
+  DifficultyScaled,
+}
+
+// What happened in a round, as far as scoring is concerned
+#[derive(Debug, Clone, Copy)]
+pub struct RoundOutcome<'a> {
+  pub player_bids: &'a PlayerBids,
+  pub optimal_solution: &'a Solution,
+  // The solver and their solution, if anyone solved the board
+  pub solved: Option<(PlayerId, &'a Solution)>,
+}
+
+impl ScoringPolicy {
+  pub fn score_round(&self, outcome: RoundOutcome) -> HashMap<PlayerId, i32> {
+    let mut deltas = HashMap::new();
+
+    if let Some((solver, solution)) = outcome.solved {
+      let points = match *self {
+        ScoringPolicy::Classic | ScoringPolicy::FailedBidPenalty { .. } => 1,
+        ScoringPolicy::OptimalBonus { bonus } => {
+          if solution.0.len() <= outcome.optimal_solution.0.len() {
+            1 + i32::from(bonus)
+          } else {
+            1
+          }
+        }
+        ScoringPolicy::DifficultyScaled => {
+          let difficulty = get_solution_difficulty(outcome.optimal_solution);
+          1 + i32::from(u8::from(difficulty))
+        }
+      };
+      deltas.insert(solver, points);
+    }
+
+    if let ScoringPolicy::FailedBidPenalty { penalty } = *self {
+      outcome
+        .player_bids
+        .bids
+        .iter()
+        .filter(|(_, bid)| matches!(bid, PlayerBid::Failed { .. }))
+        .for_each(|(player_id, _)| {
+          *deltas.entry(*player_id).or_default() -= i32::from(penalty);
+        });
+    }
+
+    deltas
+  }
+}
+
+// Scores the round with the room's policy and returns how much each player's
+// score actually changed. Scores never drop below zero, and players who have
+// since left the room are skipped.
+pub fn apply_round_score(
+  meta: &mut RoomMeta,
+  outcome: RoundOutcome,
+) -> HashMap<PlayerId, i32> {
+  meta
+    .scoring
+    .score_round(outcome)
+    .into_iter()
+    .filter_map(|(player_id, delta)| {
+      let info = meta.player_info.get_mut(&player_id)?;
+      let previous_score = info.player_score;
+      info.player_score = previous_score.saturating_add_signed(delta);
+      let change = i64::from(info.player_score) - i64::from(previous_score);
+      Some((player_id, i32::try_from(change).unwrap_or(delta)))
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod test {
+  use super::*;
+  use crate::mechanics::Direction;
+  use crate::solvers::SolutionStep;
+
+  fn solution(len: usize) -> Solution {
+    Solution(vec![
+      SolutionStep {
+        actor: 0,
+        direction: Direction::Up,
+      };
+      len
+    ])
+  }
+
+  #[test]
+  fn policies_score_solver_and_failed_bids() {
+    let player_bids = PlayerBids {
+      bids: HashMap::from([
+        (PlayerId(1), PlayerBid::Failed { value: 3 }),
+        (PlayerId(2), PlayerBid::Prospective { value: 5, order: 1 }),
+      ]),
+      timestamp: 2,
+    };
+    let optimal_solution = solution(4);
+    let solved = solution(4);
+    let outcome = RoundOutcome {
+      player_bids: &player_bids,
+      optimal_solution: &optimal_solution,
+      solved: Some((PlayerId(2), &solved)),
+    };
+
+    assert_eq!(
+      ScoringPolicy::Classic.score_round(outcome),
+      HashMap::from([(PlayerId(2), 1)])
+    );
+    assert_eq!(
+      ScoringPolicy::OptimalBonus { bonus: 2 }.score_round(outcome),
+      HashMap::from([(PlayerId(2), 3)])
+    );
+    assert_eq!(
+      ScoringPolicy::FailedBidPenalty { penalty: 1 }.score_round(outcome),
+      HashMap::from([(PlayerId(1), -1), (PlayerId(2), 1)])
+    );
+    assert_eq!(
+      ScoringPolicy::DifficultyScaled.score_round(outcome),
+      HashMap::from([(
+        PlayerId(2),
+        1 + i32::from(u8::from(get_solution_difficulty(&optimal_solution)))
+      )])
+    );
+
+    let longer = solution(5);
+    let outcome = RoundOutcome {
+      solved: Some((PlayerId(2), &longer)),
+      ..outcome
+    };
+    assert_eq!(
+      ScoringPolicy::OptimalBonus { bonus: 2 }.score_round(outcome),
+      HashMap::from([(PlayerId(2), 1)])
+    );
+  }
+}
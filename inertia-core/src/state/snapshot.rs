@@ -0,0 +1,424 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::board_generators::GeneratorSpec;
+use crate::mechanics::Position;
+use crate::solvers::Solution;
+use crate::state::data::GameLength;
+use crate::state::data::GameOver;
+use crate::state::data::PlayerBid;
+use crate::state::data::PlayerBids;
+use crate::state::data::PlayerId;
+use crate::state::data::PlayerInfo;
+use crate::state::data::PlayerName;
+use crate::state::data::PlayerReconnectKey;
+use crate::state::data::RoomId;
+use crate::state::data::RoomMeta;
+use crate::state::data::RoomState;
+use crate::state::data::RoundBidding;
+use crate::state::data::RoundResult;
+use crate::state::data::RoundSolving;
+use crate::state::data::RoundStart;
+use crate::state::data::RoundSummary;
+use crate::state::data::RoundTimers;
+use crate::state::data::RoundTimings;
+use crate::state::data::Standing;
+use crate::state::scoring::ScoringPolicy;
+use crate::state::view::SolutionVisibility;
+
+// Snapshots hold everything needed to restore a room, including what is kept
+// from clients, so they're only for the server to store. The room state types
+// themselves only serialize what clients may see. Bump the version whenever
+// the format changes.
+pub const SNAPSHOT_VERSION: u32 = 2;
+
+#[derive(Error, Debug)]
+#[error(
+  "Snapshot version {} is not supported, expected {}",
+  .0,
+  SNAPSHOT_VERSION
+)]
+pub struct SnapshotVersionError(pub u32);
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RoomSnapshot {
+  pub version: u32,
+  pub state: RoomStateSnapshot,
+}
+
+impl From<RoomState> for RoomSnapshot {
+  fn from(state: RoomState) -> Self {
+    RoomSnapshot {
+      version: SNAPSHOT_VERSION,
+      state: RoomStateSnapshot::from(state),
+    }
+  }
+}
+
+impl TryFrom<RoomSnapshot> for RoomState {
+  type Error = SnapshotVersionError;
+
+  fn try_from(snapshot: RoomSnapshot) -> Result<Self, Self::Error> {
+    if snapshot.version != SNAPSHOT_VERSION {
+      return Err(SnapshotVersionError(snapshot.version));
+    }
+    Ok(RoomState::from(snapshot.state))
+  }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PlayerInfoSnapshot {
+  pub player_id: PlayerId,
+  pub player_name: PlayerName,
+  pub player_reconnect_key: PlayerReconnectKey,
+  pub player_last_seen: u32,
+  pub player_connected: bool,
+  pub player_score: u32,
+}
+
+impl From<PlayerInfo> for PlayerInfoSnapshot {
+  fn from(info: PlayerInfo) -> Self {
+    PlayerInfoSnapshot {
+      player_id: info.player_id,
+      player_name: info.player_name,
+      player_reconnect_key: info.player_reconnect_key,
+      player_last_seen: info.player_last_seen,
+      player_connected: info.player_connected,
+      player_score: info.player_score,
+    }
+  }
+}
+
+impl From<PlayerInfoSnapshot> for PlayerInfo {
+  fn from(info: PlayerInfoSnapshot) -> Self {
+    PlayerInfo {
+      player_id: info.player_id,
+      player_name: info.player_name,
+      player_reconnect_key: info.player_reconnect_key,
+      player_last_seen: info.player_last_seen,
+      player_connected: info.player_connected,
+      player_score: info.player_score,
+    }
+  }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PlayerBidsSnapshot {
+  pub bids: HashMap<PlayerId, PlayerBid>,
+  pub timestamp: u32,
+}
+
+impl From<PlayerBids> for PlayerBidsSnapshot {
+  fn from(player_bids: PlayerBids) -> Self {
+    PlayerBidsSnapshot {
+      bids: player_bids.bids,
+      timestamp: player_bids.timestamp,
+    }
+  }
+}
+
+impl From<PlayerBidsSnapshot> for PlayerBids {
+  fn from(player_bids: PlayerBidsSnapshot) -> Self {
+    PlayerBids {
+      bids: player_bids.bids,
+      timestamp: player_bids.timestamp,
+    }
+  }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RoundResultSnapshot {
+  pub round_number: u32,
+  pub board: Position,
+  pub optimal_solution: Solution,
+  pub player_bids: PlayerBidsSnapshot,
+  pub solver: Option<PlayerId>,
+  pub solution: Option<Solution>,
+  pub score_deltas: HashMap<PlayerId, i32>,
+  pub timings: RoundTimings,
+}
+
+impl From<RoundResult> for RoundResultSnapshot {
+  fn from(round: RoundResult) -> Self {
+    RoundResultSnapshot {
+      round_number: round.round_number,
+      board: round.board,
+      optimal_solution: round.optimal_solution,
+      player_bids: round.player_bids.into(),
+      solver: round.solver,
+      solution: round.solution,
+      score_deltas: round.score_deltas,
+      timings: round.timings,
+    }
+  }
+}
+
+impl From<RoundResultSnapshot> for RoundResult {
+  fn from(round: RoundResultSnapshot) -> Self {
+    RoundResult {
+      round_number: round.round_number,
+      board: round.board,
+      optimal_solution: round.optimal_solution,
+      player_bids: round.player_bids.into(),
+      solver: round.solver,
+      solution: round.solution,
+      score_deltas: round.score_deltas,
+      timings: round.timings,
+    }
+  }
+}
+
+fn convert_all<T, U, C>(items: C) -> Vec<U>
+where
+  C: IntoIterator<Item = T>,
+  U: From<T>,
+{
+  items.into_iter().map(U::from).collect()
+}
+
+fn convert_players<T, U>(players: HashMap<PlayerId, T>) -> HashMap<PlayerId, U>
+where
+  U: From<T>,
+{
+  players
+    .into_iter()
+    .map(|(player_id, info)| (player_id, U::from(info)))
+    .collect()
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct RoomMetaSnapshot {
+  pub room_id: RoomId,
+  pub generator_spec: GeneratorSpec,
+  // Left out of snapshots from before it was kept
+  #[serde(default)]
+  pub boards_drawn: u32,
+  pub timers: RoundTimers,
+  pub scoring: ScoringPolicy,
+  pub game_length: GameLength,
+  pub solution_visibility: SolutionVisibility,
+  pub time_limit_reached: bool,
+  pub game_started_at: Option<u64>,
+  pub round_history: Vec<RoundResultSnapshot>,
+  pub round_timings: RoundTimings,
+  pub player_info: HashMap<PlayerId, PlayerInfoSnapshot>,
+  pub spectator_info: HashMap<PlayerId, PlayerInfoSnapshot>,
+  // Left out of snapshots from before it was kept
+  #[serde(default)]
+  pub kicked_players: HashMap<PlayerId, PlayerReconnectKey>,
+  pub host: Option<PlayerId>,
+  pub round_number: u32,
+}
+
+impl From<RoomMeta> for RoomMetaSnapshot {
+  fn from(meta: RoomMeta) -> Self {
+    RoomMetaSnapshot {
+      room_id: meta.room_id,
+      generator_spec: meta.generator_spec,
+      boards_drawn: meta.boards_drawn,
+      timers: meta.timers,
+      scoring: meta.scoring,
+      game_length: meta.game_length,
+      solution_visibility: meta.solution_visibility,
+      time_limit_reached: meta.time_limit_reached,
+      game_started_at: meta.game_started_at,
+      round_history: convert_all(meta.round_history),
+      round_timings: meta.round_timings,
+      player_info: convert_players(meta.player_info),
+      spectator_info: convert_players(meta.spectator_info),
+      kicked_players: meta.kicked_players,
+      host: meta.host,
+      round_number: meta.round_number,
+    }
+  }
+}
+
+impl From<RoomMetaSnapshot> for RoomMeta {
+  fn from(meta: RoomMetaSnapshot) -> Self {
+    RoomMeta {
+      room_id: meta.room_id,
+      generator_spec: meta.generator_spec,
+      boards_drawn: meta.boards_drawn,
+      timers: meta.timers,
+      scoring: meta.scoring,
+      game_length: meta.game_length,
+      solution_visibility: meta.solution_visibility,
+      time_limit_reached: meta.time_limit_reached,
+      game_started_at: meta.game_started_at,
+      round_history: convert_all(meta.round_history),
+      round_timings: meta.round_timings,
+      player_info: convert_players(meta.player_info),
+      spectator_info: convert_players(meta.spectator_info),
+      kicked_players: meta.kicked_players,
+      host: meta.host,
+      round_number: meta.round_number,
+    }
+  }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", content = "content")]
+pub enum RoomStateSnapshot {
+  None,
+  Closed,
+  RoundSummary {
+    meta: RoomMetaSnapshot,
+    last_round_board: Option<Position>,
+    last_round_solution: Option<Solution>,
+    last_solver: Option<PlayerId>,
+    last_round_optimal_solution: Option<Solution>,
+    last_round_score_deltas: HashMap<PlayerId, i32>,
+  },
+  RoundStart {
+    meta: RoomMetaSnapshot,
+    board: Position,
+    optimal_solution: Solution,
+  },
+  RoundBidding {
+    meta: RoomMetaSnapshot,
+    board: Position,
+    player_bids: PlayerBidsSnapshot,
+    optimal_solution: Solution,
+  },
+  RoundSolving {
+    meta: RoomMetaSnapshot,
+    board: Position,
+    player_bids: PlayerBidsSnapshot,
+    solver: PlayerId,
+    solution: Solution,
+    optimal_solution: Solution,
+  },
+  GameOver {
+    meta: RoomMetaSnapshot,
+    last_round_board: Option<Position>,
+    last_round_solution: Option<Solution>,
+    last_solver: Option<PlayerId>,
+    last_round_optimal_solution: Option<Solution>,
+    standings: Vec<Standing>,
+    round_history: Vec<RoundResultSnapshot>,
+  },
+}
+
+impl From<RoomState> for RoomStateSnapshot {
+  fn from(state: RoomState) -> Self {
+    match state {
+      RoomState::None => RoomStateSnapshot::None,
+      RoomState::Closed => RoomStateSnapshot::Closed,
+      RoomState::RoundSummary(summary) => RoomStateSnapshot::RoundSummary {
+        meta: summary.meta.into(),
+        last_round_board: summary.last_round_board,
+        last_round_solution: summary.last_round_solution,
+        last_solver: summary.last_solver,
+        last_round_optimal_solution: summary.last_round_optimal_solution,
+        last_round_score_deltas: summary.last_round_score_deltas,
+      },
+      RoomState::RoundStart(start) => RoomStateSnapshot::RoundStart {
+        meta: start.meta.into(),
+        board: start.board,
+        optimal_solution: start.optimal_solution,
+      },
+      RoomState::RoundBidding(bidding) => RoomStateSnapshot::RoundBidding {
+        meta: bidding.meta.into(),
+        board: bidding.board,
+        player_bids: bidding.player_bids.into(),
+        optimal_solution: bidding.optimal_solution,
+      },
+      RoomState::RoundSolving(solving) => RoomStateSnapshot::RoundSolving {
+        meta: solving.meta.into(),
+        board: solving.board,
+        player_bids: solving.player_bids.into(),
+        solver: solving.solver,
+        solution: solving.solution,
+        optimal_solution: solving.optimal_solution,
+      },
+      RoomState::GameOver(game_over) => RoomStateSnapshot::GameOver {
+        meta: game_over.meta.into(),
+        last_round_board: game_over.last_round_board,
+        last_round_solution: game_over.last_round_solution,
+        last_solver: game_over.last_solver,
+        last_round_optimal_solution: game_over.last_round_optimal_solution,
+        standings: game_over.standings,
+        round_history: convert_all(game_over.round_history),
+      },
+    }
+  }
+}
+
+impl From<RoomStateSnapshot> for RoomState {
+  fn from(state: RoomStateSnapshot) -> Self {
+    match state {
+      RoomStateSnapshot::None => RoomState::None,
+      RoomStateSnapshot::Closed => RoomState::Closed,
+      RoomStateSnapshot::RoundSummary {
+        meta,
+        last_round_board,
+        last_round_solution,
+        last_solver,
+        last_round_optimal_solution,
+        last_round_score_deltas,
+      } => RoomState::RoundSummary(RoundSummary {
+        meta: meta.into(),
+        last_round_board,
+        last_round_solution,
+        last_solver,
+        last_round_optimal_solution,
+        last_round_score_deltas,
+      }),
+      RoomStateSnapshot::RoundStart {
+        meta,
+        board,
+        optimal_solution,
+      } => RoomState::RoundStart(RoundStart {
+        meta: meta.into(),
+        board,
+        optimal_solution,
+      }),
+      RoomStateSnapshot::RoundBidding {
+        meta,
+        board,
+        player_bids,
+        optimal_solution,
+      } => RoomState::RoundBidding(RoundBidding {
+        meta: meta.into(),
+        board,
+        player_bids: player_bids.into(),
+        optimal_solution,
+      }),
+      RoomStateSnapshot::RoundSolving {
+        meta,
+        board,
+        player_bids,
+        solver,
+        solution,
+        optimal_solution,
+      } => RoomState::RoundSolving(RoundSolving {
+        meta: meta.into(),
+        board,
+        player_bids: player_bids.into(),
+        solver,
+        solution,
+        optimal_solution,
+      }),
+      RoomStateSnapshot::GameOver {
+        meta,
+        last_round_board,
+        last_round_solution,
+        last_solver,
+        last_round_optimal_solution,
+        standings,
+        round_history,
+      } => RoomState::GameOver(GameOver {
+        meta: meta.into(),
+        last_round_board,
+        last_round_solution,
+        last_solver,
+        last_round_optimal_solution,
+        standings,
+        round_history: convert_all(round_history),
+      }),
+    }
+  }
+}
@@ -0,0 +1,100 @@
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::state::data::PlayerId;
+use crate::state::data::PlayerRole;
+use crate::state::data::RoomState;
+use crate::state::data::RoundSolving;
+
+#[cfg(feature = "web")]
+use {tsify::Tsify, wasm_bindgen::prelude::wasm_bindgen};
+
+// Who gets to watch the solver's moves while they're solving. The solver
+// always sees their own moves, and everyone sees the full solution once the
+// round is over.
+#[derive(
+  Serialize, Deserialize, Default, Eq, PartialEq, Copy, Clone, Debug,
+)]
+#[cfg_attr(feature = "web", derive(Tsify), tsify(into_wasm_abi, from_wasm_abi))]
+#[serde(tag = "type", content = "content")]
+pub enum SolutionVisibility {
+  #[default]
+  Everyone,
+  SolverOnly,
+  // Everyone else sees the solution this many moves behind the solver
+  Delayed {
+    moves: u8,
+  },
+}
+
+impl SolutionVisibility {
+  fn visible_moves(&self, solution_len: usize) -> usize {
+    match *self {
+      SolutionVisibility::Everyone => solution_len,
+      SolutionVisibility::SolverOnly => 0,
+      SolutionVisibility::Delayed { moves } => {
+        solution_len.saturating_sub(usize::from(moves))
+      }
+    }
+  }
+}
+
+// A solver who is also the host sees the room as the solver
+#[derive(Serialize, Deserialize, Eq, PartialEq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "web", derive(Tsify), tsify(into_wasm_abi, from_wasm_abi))]
+pub enum ViewerRole {
+  Player,
+  Solver,
+  Spectator,
+  Host,
+}
+
+// The room as one connection gets to see it
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "web", derive(Tsify), tsify(into_wasm_abi))]
+pub struct RoomView {
+  pub viewer_id: PlayerId,
+  // None if the viewer isn't in the room
+  pub viewer_role: Option<ViewerRole>,
+  // How many of the solver's moves are kept from the viewer
+  pub hidden_moves: usize,
+  pub state: RoomState,
+}
+
+impl RoomState {
+  pub fn viewer_role(&self, viewer_id: PlayerId) -> Option<ViewerRole> {
+    let meta = self.get_meta()?;
+    let role = meta.get_role(viewer_id)?;
+    if self.get_solver() == Some(viewer_id) {
+      Some(ViewerRole::Solver)
+    } else if meta.is_host(viewer_id) {
+      Some(ViewerRole::Host)
+    } else if role == PlayerRole::Spectator {
+      Some(ViewerRole::Spectator)
+    } else {
+      Some(ViewerRole::Player)
+    }
+  }
+
+  pub fn view(&self, viewer_id: PlayerId) -> RoomView {
+    let viewer_role = self.viewer_role(viewer_id);
+    let mut state = self.clone();
+    let mut hidden_moves = 0;
+    if let RoomState::RoundSolving(RoundSolving { meta, solution, .. }) =
+      &mut state
+    {
+      if viewer_role != Some(ViewerRole::Solver) {
+        let visible_moves =
+          meta.solution_visibility.visible_moves(solution.0.len());
+        hidden_moves = solution.0.len() - visible_moves;
+        solution.0.truncate(visible_moves);
+      }
+    }
+    RoomView {
+      viewer_id,
+      viewer_role,
+      hidden_moves,
+      state,
+    }
+  }
+}
@@ -0,0 +1,27 @@
+use rand::RngCore;
+
+use crate::mechanics::BoardUnavailableError;
+use crate::mechanics::SolvedPosition;
+use crate::mechanics::SolvedPositionGenerator;
+
+// Never has a board, like a database generator that can't reach its database
+#[derive(Clone, Debug)]
+pub struct UnavailableGenerator;
+
+impl SolvedPositionGenerator for UnavailableGenerator {
+  fn generate_solved_position_with_rng(
+    &self,
+    rng: &mut dyn RngCore,
+  ) -> SolvedPosition {
+    self
+      .try_generate_solved_position_with_rng(rng)
+      .expect("UnavailableGenerator never has a board")
+  }
+
+  fn try_generate_solved_position_with_rng(
+    &self,
+    _rng: &mut dyn RngCore,
+  ) -> Result<SolvedPosition, BoardUnavailableError> {
+    Err(BoardUnavailableError)
+  }
+}
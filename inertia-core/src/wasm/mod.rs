@@ -11,6 +11,7 @@ use crate::mechanics::ExpandedBitBoard;
 use crate::mechanics::MoveBoard;
 use crate::mechanics::Position;
 use crate::mechanics::Square;
+use crate::solvers::all_goals;
 use crate::solvers::astar;
 use crate::solvers::difficulty::get_solution_difficulty;
 use crate::solvers::B64EncodedCompressedSolution;
@@ -21,6 +22,7 @@ use crate::solvers::HeuristicValue;
 use crate::solvers::MinAssistsBoard;
 use crate::solvers::MinCrawlsBoard;
 use crate::solvers::MinMovesBoard;
+use crate::solvers::NoSolutionReason;
 use crate::solvers::Solution;
 use crate::state::data::PlayerBids;
 use serde::Deserialize;
@@ -29,6 +31,10 @@ use serde_with::serde_as;
 use tsify::Tsify;
 use wasm_bindgen::prelude::*;
 
+// Searching for every goal at once gets much slower with depth, and the board
+// editor shows it as the board is edited
+const ALL_GOALS_MAX_DEPTH: usize = 8;
+
 #[serde_as]
 #[derive(Debug, Serialize, Deserialize, Tsify)]
 #[tsify(into_wasm_abi, from_wasm_abi)]
@@ -44,6 +50,14 @@ pub struct MetaBoardWrapper {
   squares: [HeuristicValue; 256],
 }
 
+#[derive(Debug, Serialize, Deserialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+#[serde(tag = "type", content = "content")]
+pub enum SolveResult {
+  Solved(Solution),
+  NoSolution(NoSolutionReason),
+}
+
 #[wasm_bindgen]
 pub fn set_panic_hook() {
   #[cfg(feature = "console_error_panic_hook")]
@@ -189,7 +203,14 @@ pub fn get_min_crawls_board(board_position: Position) -> MetaBoardWrapper {
 }
 
 #[wasm_bindgen]
-pub fn solve(board_position: Position) -> Option<Solution> {
+pub fn get_all_goals_board(board_position: Position) -> MetaBoardWrapper {
+  let squares =
+    all_goals::solve_position_all_goals(board_position, ALL_GOALS_MAX_DEPTH);
+  MetaBoardWrapper { squares }
+}
+
+#[wasm_bindgen]
+pub fn solve(board_position: Position) -> SolveResult {
   let Position {
     walled_board,
     actor_squares,
@@ -197,5 +218,8 @@ pub fn solve(board_position: Position) -> Option<Solution> {
   } = board_position;
   let board = MoveBoard::from(&walled_board);
 
-  astar::solve(&board, goal, actor_squares, 255)
+  match astar::try_solve(&board, goal, actor_squares, 255) {
+    Ok(solution) => SolveResult::Solved(solution),
+    Err(reason) => SolveResult::NoSolution(reason),
+  }
 }
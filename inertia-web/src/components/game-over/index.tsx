@@ -0,0 +1,68 @@
+import { GameOver as GameOverState, PlayerId, Position } from 'inertia-core';
+import { BlockText } from '../block-text';
+import { Divider } from '../divider';
+import { FlexCenter } from '../flex-center';
+import { PanelTitle } from '../panel-title';
+import { PlayableBoard } from '../playable-board';
+import { Scoreboard } from '../scoreboard';
+import { ThemedButton } from '../themed-form';
+import { ThemedPanel } from '../themed-panel';
+import { RenderWhen } from '../utils/RenderWhen';
+import { exportRoundHistory } from '../../utils/export';
+
+export const GameOver = ({
+  state,
+  userPlayerId,
+  position,
+  onNewGame,
+}: {
+  state: GameOverState;
+  userPlayerId: PlayerId;
+  position: Position;
+  onNewGame: () => void;
+}) => {
+  const roundsPlayed = state.meta.round_number;
+  const isHost = state.meta.host === userPlayerId;
+  const onExportRounds = () =>
+    exportRoundHistory(state.meta.room_id, state.round_history);
+
+  return (
+    <FlexCenter wrap>
+      <FlexCenter wrap>
+        <Scoreboard
+          players={state.meta.player_info}
+          userPlayerId={userPlayerId}
+        />
+        <ThemedPanel>
+          <FlexCenter column>
+            <PanelTitle>Game Over</PanelTitle>
+            <Divider />
+            <BlockText>
+              {`${roundsPlayed} ${
+                roundsPlayed === 1 ? 'round was' : 'rounds were'
+              } played.`}
+            </BlockText>
+            <Divider />
+            {state.standings.map((standing) => (
+              <BlockText key={standing.player_id}>
+                {`${standing.rank}. ${standing.player_name} - ${standing.player_score}`}
+              </BlockText>
+            ))}
+            <Divider />
+            <FlexCenter>
+              <RenderWhen when={state.round_history.length > 0}>
+                <ThemedButton onClick={onExportRounds}>
+                  Export Rounds
+                </ThemedButton>
+              </RenderWhen>
+              <RenderWhen when={isHost}>
+                <ThemedButton onClick={onNewGame}>New Game</ThemedButton>
+              </RenderWhen>
+            </FlexCenter>
+          </FlexCenter>
+        </ThemedPanel>
+      </FlexCenter>
+      <PlayableBoard position={position} />
+    </FlexCenter>
+  );
+};
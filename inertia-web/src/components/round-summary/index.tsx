@@ -3,6 +3,7 @@ import {
   encode_solution,
   get_difficulty,
   PlayerId,
+  PlayerRole,
   Position,
   RoundSummary as RoundSummaryState,
 } from 'inertia-core';
@@ -15,18 +16,32 @@ import { Scoreboard } from '../scoreboard';
 import { ThemedButton } from '../themed-form';
 import { ThemedPanel } from '../themed-panel';
 import { RenderWhen } from '../utils/RenderWhen';
+import { exportRoundHistory } from '../../utils/export';
 
 export const RoundSummary = ({
   state,
   userPlayerId,
   position,
   onStartRound,
+  onSetRole,
+  onEndGame,
 }: {
   state: RoundSummaryState;
   userPlayerId: PlayerId;
   position: Position;
   onStartRound: () => void;
+  onSetRole: (role: PlayerRole) => void;
+  onEndGame: () => void;
 }) => {
+  const isSpectator = state.meta.spectator_info[userPlayerId] != null;
+  const isHost = state.meta.host === userPlayerId;
+  const hostName =
+    state.meta.host != null
+      ? state.meta.player_info[state.meta.host]?.player_name ?? 'unknown'
+      : 'nobody';
+  const spectatorNames = Object.values(state.meta.spectator_info)
+    .map((info) => info.player_name)
+    .join(', ');
   const roundPanelTitle =
     state.meta.round_number === 0
       ? 'Lobby'
@@ -41,10 +56,22 @@ export const RoundSummary = ({
       : 'unknown';
   const lastRoundOptimalSolutionMoves =
     state.last_round_optimal_solution?.length ?? -1;
+  const scoreChanges = Object.entries(state.last_round_score_deltas)
+    .filter(([, delta]) => delta !== 0)
+    .map(([playerId, delta]) => {
+      const name =
+        state.meta.player_info[Number(playerId)]?.player_name ?? 'unknown';
+      return `${name} ${delta > 0 ? '+' : ''}${delta}`;
+    })
+    .join(', ');
   const lastRoundDifficulty = state.last_round_optimal_solution
     ? get_difficulty(state.last_round_optimal_solution)
     : 'unknown';
 
+  const roundHistory = state.meta.round_history;
+  const onExportRounds = () =>
+    exportRoundHistory(state.meta.room_id, roundHistory);
+
   const openBoardExplorer = () => {
     const boardExplorerParams = new URLSearchParams();
     if (state.last_round_board) {
@@ -99,6 +126,9 @@ export const RoundSummary = ({
                 }`}
               </BlockText>
               <BlockText>{`Difficulty: ${lastRoundDifficulty}`}</BlockText>
+              <RenderWhen when={scoreChanges.length > 0}>
+                <BlockText>{`Score changes: ${scoreChanges}`}</BlockText>
+              </RenderWhen>
             </RenderWhen>
             <Divider />
             <FlexCenter>
@@ -107,10 +137,29 @@ export const RoundSummary = ({
                   View in Board Explorer
                 </ThemedButton>
               </RenderWhen>
-              <ThemedButton onClick={onStartRound}>
-                {roundStartButtonText}
+              <RenderWhen when={roundHistory.length > 0}>
+                <ThemedButton onClick={onExportRounds}>
+                  Export Rounds
+                </ThemedButton>
+              </RenderWhen>
+              <RenderWhen when={isHost}>
+                <ThemedButton onClick={onStartRound}>
+                  {roundStartButtonText}
+                </ThemedButton>
+                <ThemedButton onClick={onEndGame}>End Game</ThemedButton>
+              </RenderWhen>
+              <ThemedButton
+                onClick={() => onSetRole(isSpectator ? 'Player' : 'Spectator')}
+              >
+                {isSpectator ? 'Join as Player' : 'Spectate'}
               </ThemedButton>
             </FlexCenter>
+            <RenderWhen when={!isHost}>
+              <BlockText>{`Waiting for ${hostName} to start`}</BlockText>
+            </RenderWhen>
+            <RenderWhen when={spectatorNames.length > 0}>
+              <BlockText>{`Spectating: ${spectatorNames}`}</BlockText>
+            </RenderWhen>
           </FlexCenter>
         </ThemedPanel>
       </FlexCenter>
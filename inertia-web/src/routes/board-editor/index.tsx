@@ -1,4 +1,9 @@
-import { ActorSquares, MetaBoardWrapper, WalledBoard } from 'inertia-core';
+import {
+  ActorSquares,
+  MetaBoardWrapper,
+  Position,
+  WalledBoard,
+} from 'inertia-core';
 import {
   encode_position,
   encode_solution,
@@ -7,8 +12,9 @@ import {
   get_min_moves_board,
   solve,
   get_min_crawls_board,
+  get_all_goals_board,
 } from 'inertia-core';
-import { useEffect, useState } from 'preact/hooks';
+import { useEffect, useMemo, useState } from 'preact/hooks';
 import { Divider } from '../../components/divider';
 import { ErrorPage } from '../../components/error-page';
 import { FlexCenter } from '../../components/flex-center';
@@ -54,8 +60,27 @@ const toggleWall = (
   }
 };
 
+const getMetaBoard = (
+  metaBoardType: string,
+  position: Position,
+): MetaBoardWrapper | undefined => {
+  if (metaBoardType === 'min_moves') {
+    return get_min_moves_board(position);
+  } else if (metaBoardType === 'group_min_moves') {
+    return get_group_min_moves_board(position);
+  } else if (metaBoardType === 'min_assists') {
+    return get_min_assists_board(position);
+  } else if (metaBoardType === 'min_crawls') {
+    return get_min_crawls_board(position);
+  } else if (metaBoardType === 'all_goals') {
+    return get_all_goals_board(position);
+  }
+  return undefined;
+};
+
 export const BoardEditor = () => {
   const [metaBoardType, setMetaBoardType] = useState('');
+  const [solveMessage, setSolveMessage] = useState('');
 
   const [selection, setSelection] = useState(BoardSelection.NONE);
   useClickAwayDeselect(setSelection);
@@ -70,6 +95,12 @@ export const BoardEditor = () => {
   }, []);
 
   const position = positions[0]?.position;
+  // Meta boards can take a while to work out, so they're only redone when the
+  // position or the chosen meta board changes rather than on every hover
+  const metaBoard = useMemo(
+    () => (position ? getMetaBoard(metaBoardType, position) : undefined),
+    [metaBoardType, position],
+  );
   if (!position) {
     return (
       <>
@@ -90,17 +121,26 @@ export const BoardEditor = () => {
     const boardExplorerParams = new URLSearchParams();
     boardExplorerParams.append('position', encode_position(position));
     const start = Date.now();
-    const solution = solve(position);
+    const result = solve(position);
     const end = Date.now();
-    if (solution) {
-      console.log(
-        `Solution of length ${solution?.length} solved in ${(end - start) / 1000} seconds.`,
-      );
-      boardExplorerParams.append(
-        'solution',
-        `${encode_solution(solution)}:Optimal`,
-      );
+    if (result.type === 'NoSolution') {
+      const reason =
+        result.content === 'Unsolvable'
+          ? 'No solution exists for this position.'
+          : 'No solution exists within the maximum search depth.';
+      console.log(`${reason} Solved in ${(end - start) / 1000} seconds.`);
+      setSolveMessage(reason);
+      return;
     }
+    const solution = result.content;
+    console.log(
+      `Solution of length ${solution.length} solved in ${(end - start) / 1000} seconds.`,
+    );
+    setSolveMessage('');
+    boardExplorerParams.append(
+      'solution',
+      `${encode_solution(solution)}:Optimal`,
+    );
     const boardExplorerUrl = `/explore?${boardExplorerParams.toString()}`;
     window.open(boardExplorerUrl, '_blank');
   };
@@ -191,17 +231,6 @@ export const BoardEditor = () => {
     setMouseOverIndicatorWall(emptyBoard());
   };
 
-  let metaBoard: MetaBoardWrapper | undefined;
-  if (metaBoardType === 'min_moves') {
-    metaBoard = get_min_moves_board(position);
-  } else if (metaBoardType === 'group_min_moves') {
-    metaBoard = get_group_min_moves_board(position);
-  } else if (metaBoardType === 'min_assists') {
-    metaBoard = get_min_assists_board(position);
-  } else if (metaBoardType === 'min_crawls') {
-    metaBoard = get_min_crawls_board(position);
-  }
-
   return (
     <>
       <Starfield numStars={500} speed={0.5} />
@@ -217,6 +246,7 @@ export const BoardEditor = () => {
             <ThemedButton onClick={openSolvedInBoardExplorer}>
               Solve in Board Explorer
             </ThemedButton>
+            {solveMessage && <div>{solveMessage}</div>}
             <Divider />
             MetaBoard Options
             <ThemedFormLine>
@@ -235,6 +265,9 @@ export const BoardEditor = () => {
               <ThemedButton onClick={() => setMetaBoardType('min_crawls')}>
                 Min Crawls
               </ThemedButton>
+              <ThemedButton onClick={() => setMetaBoardType('all_goals')}>
+                All Goals
+              </ThemedButton>
             </ThemedFormLine>
           </FlexCenter>
         </ThemedPanel>
@@ -1,5 +1,6 @@
 import {
   apply_solution,
+  PlayerRole,
   RoomState,
   RoundSolving as RoundSolvingState,
   SolutionStep,
@@ -7,6 +8,7 @@ import {
 } from 'inertia-core';
 import { useEffect, useMemo, useRef, useState } from 'preact/hooks';
 import { ErrorPage } from '../../components/error-page';
+import { GameOver } from '../../components/game-over';
 import { AppControls } from '../../components/room-controls';
 import { RoundBidding } from '../../components/round-bidding';
 import { RoundSolving } from '../../components/round-solving';
@@ -30,6 +32,7 @@ const RoomStateType = {
   ROUND_START: 'RoundStart',
   ROUND_BIDDING: 'RoundBidding',
   ROUND_SOLVING: 'RoundSolving',
+  GAME_OVER: 'GameOver',
 } as const;
 
 export const Room = ({ roomId: roomIdString }: { roomId: string }) => {
@@ -47,11 +50,15 @@ export const Room = ({ roomId: roomIdString }: { roomId: string }) => {
   const [countdownTimeLeft, setCountdownTimeLeft] = useState<number | null>(
     null,
   );
+  const [errorMessage, setErrorMessage] = useState<string | null>(null);
 
   const initialPosition = useMemo(() => {
     if (roomState.type === 'None' || roomState.type === 'Closed') {
       return defaultPosition();
-    } else if (roomState.type === 'RoundSummary') {
+    } else if (
+      roomState.type === 'RoundSummary' ||
+      roomState.type === 'GameOver'
+    ) {
       return roomState.content.last_round_board ?? defaultPosition();
     } else {
       return roomState.content.board;
@@ -65,7 +72,10 @@ export const Room = ({ roomId: roomIdString }: { roomId: string }) => {
   const serverSolution: SolutionStep[] = useMemo(() => {
     if (roomState.type === 'RoundSolving') {
       return roomState.content.solution;
-    } else if (roomState.type === 'RoundSummary') {
+    } else if (
+      roomState.type === 'RoundSummary' ||
+      roomState.type === 'GameOver'
+    ) {
       return roomState.content.last_round_solution ?? [];
     } else {
       return [];
@@ -137,6 +147,7 @@ export const Room = ({ roomId: roomIdString }: { roomId: string }) => {
     const urlParams = new URLSearchParams(window.location.search);
     const minDifficulty = parseDifficulty(urlParams.get('minDifficulty'));
     const maxDifficulty = parseDifficulty(urlParams.get('maxDifficulty'));
+    const isSpectator = urlParams.has('spectate');
 
     websocket.current = new RoomWebSocket();
     const ws = websocket.current;
@@ -150,14 +161,21 @@ export const Room = ({ roomId: roomIdString }: { roomId: string }) => {
           room_id: roomId,
           min_difficulty: minDifficulty ?? null,
           max_difficulty: maxDifficulty ?? null,
+          generator: null,
+          timers: null,
+          scoring: null,
+          game_length: null,
+          role: isSpectator ? 'Spectator' : null,
         },
       });
     });
     ws.onMessage((msg: ToClientMessage) => {
       if (msg.type === 'RoomUpdate') {
-        setRoomState(msg.content);
+        setRoomState(msg.content.state);
       } else if (msg.type === 'CountdownUpdate') {
         setCountdownTimeLeft(msg.content.server_time_left_millis);
+      } else if (msg.type === 'Error') {
+        setErrorMessage(msg.content.message);
       }
     });
     return () => {
@@ -180,6 +198,30 @@ export const Room = ({ roomId: roomIdString }: { roomId: string }) => {
       });
     });
 
+  const onSetRole = (role: PlayerRole) =>
+    withWs((ws) => {
+      ws.send({
+        type: 'SetRole',
+        content: {
+          role,
+        },
+      });
+    });
+
+  const onEndGame = () =>
+    withWs((ws) => {
+      ws.send({
+        type: 'EndGame',
+      });
+    });
+
+  const onNewGame = () =>
+    withWs((ws) => {
+      ws.send({
+        type: 'NewGame',
+      });
+    });
+
   const onBid = (bid: number) =>
     withWs((ws) => {
       ws.send({
@@ -232,6 +274,8 @@ export const Room = ({ roomId: roomIdString }: { roomId: string }) => {
           userPlayerId={userPlayerId}
           position={position}
           onStartRound={onStartRound}
+          onSetRole={onSetRole}
+          onEndGame={onEndGame}
         />
       );
     }
@@ -277,8 +321,19 @@ export const Room = ({ roomId: roomIdString }: { roomId: string }) => {
       );
     }
 
+    if (roomState.type === RoomStateType.GAME_OVER) {
+      return (
+        <GameOver
+          state={roomState.content}
+          userPlayerId={userPlayerId}
+          position={position}
+          onNewGame={onNewGame}
+        />
+      );
+    }
+
     if (roomState.type === RoomStateType.NONE) {
-      return <ErrorPage>Nothing here.</ErrorPage>;
+      return <ErrorPage>{errorMessage ?? 'Nothing here.'}</ErrorPage>;
     }
 
     if (roomState.type === RoomStateType.CLOSED) {
@@ -0,0 +1,13 @@
+import { RoundResult } from 'inertia-core';
+
+export const exportRoundHistory = (roomId: number, rounds: RoundResult[]) => {
+  const blob = new Blob([JSON.stringify(rounds, null, 2)], {
+    type: 'application/json',
+  });
+  const url = URL.createObjectURL(blob);
+  const link = document.createElement('a');
+  link.href = url;
+  link.download = `inertia-room-${roomId}-rounds.json`;
+  link.click();
+  URL.revokeObjectURL(url);
+};